@@ -2,10 +2,28 @@ use crate::register_commands;
 use async_trait::async_trait;
 
 #[async_trait]
+#[allow(clippy::double_must_use)]
 pub(crate) trait Command {
     async fn run(&self) -> Result<(), ()>;
 }
 
 register_commands!(
-    new, init, add, remove, install, uninstall, shell, mount, umount, lock, list, store
+    new,
+    init,
+    add,
+    remove,
+    install,
+    uninstall,
+    shell,
+    mount,
+    umount,
+    lock,
+    list,
+    store,
+    dedupe,
+    deprecations,
+    graph,
+    why,
+    login,
+    logout
 );
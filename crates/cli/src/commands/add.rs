@@ -1,17 +1,26 @@
-use crate::{Command, utils::parse_package_str};
+use crate::{
+    Command,
+    utils::{ResolveArgs, check_engines, check_failures, parse_package_str},
+};
 use async_trait::async_trait;
 
 use clap::Args;
 
 use std::time::Instant;
 
-use resolver::graph::DAGBuilder;
+use resolver::{deprecations, graph::DependencyGraph};
 use store::Store;
 use utils::logger::*;
 
 #[derive(Debug, Args)]
 pub(crate) struct AddCommand {
     packages: Vec<String>,
+
+    #[clap(long, default_value_t = false)]
+    include_prerelease: bool,
+
+    #[clap(flatten)]
+    resolve: ResolveArgs,
 }
 
 #[async_trait]
@@ -38,10 +47,18 @@ impl Command for AddCommand {
             info(format!("Skipping {existing_count} already installed packages"), false);
         }
 
-        let builder = DAGBuilder::new();
-        let resolution_results = builder.build_missing_only(missing_packages).await;
+        let builder = self.resolve.builder(self.include_prerelease)?;
+        let resolution = self.resolve.report(builder.resolve("project", missing_packages).await)?;
+
+        check_engines(resolution.packages.values(), self.resolve.engine_strict)?;
+
+        let graph = DependencyGraph::from_lockfile(&resolution.lockfile, &resolution.packages);
+        deprecations::report(&deprecations::collect(&graph), "project");
+
+        let (installed, failed) =
+            store.add_packages(resolution.packages.into_values().collect()).await;
 
-        let installed = store.add_packages(resolution_results).await;
+        check_failures(&failed, &resolution.lockfile, &[])?;
 
         if !installed.is_empty() {
             success(format!("Installed {} packages", installed.len()), false);
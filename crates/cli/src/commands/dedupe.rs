@@ -0,0 +1,44 @@
+use crate::Command;
+use async_trait::async_trait;
+
+use clap::Args;
+
+use std::path::Path;
+
+use resolver::{
+    dedupe::{dedupe, report},
+    lockfile::{LOCKFILE_NAME, Lockfile},
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct DedupeCommand {}
+
+#[async_trait]
+impl Command for DedupeCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let lock_path = Path::new(LOCKFILE_NAME);
+
+        let Some(mut lockfile) = Lockfile::read(lock_path) else {
+            error(format!("Could not read {LOCKFILE_NAME}, run 'qp lock' first"), false);
+            return Err(());
+        };
+
+        let merges = dedupe(&mut lockfile);
+        if merges.is_empty() {
+            success("No duplicate packages found", false);
+            return Ok(());
+        }
+
+        report(&merges);
+
+        if lockfile.write(lock_path).is_err() {
+            error(format!("Could not write {LOCKFILE_NAME}"), false);
+            return Err(());
+        }
+
+        success(format!("{LOCKFILE_NAME} updated"), false);
+
+        Ok(())
+    }
+}
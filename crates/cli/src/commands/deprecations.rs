@@ -0,0 +1,49 @@
+use crate::Command;
+use async_trait::async_trait;
+
+use clap::Args;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use resolver::{
+    deprecations::{collect, report},
+    graph::DependencyGraph,
+    lockfile::{LOCKFILE_NAME, Lockfile},
+    manifest::Manifest,
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct DeprecationsCommand {
+    #[clap(long, default_value_t = false)]
+    json: bool,
+}
+
+#[async_trait]
+impl Command for DeprecationsCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let Some(lockfile) = Lockfile::read(Path::new(LOCKFILE_NAME)) else {
+            error(format!("Could not read {LOCKFILE_NAME}, run 'qp lock' first"), false);
+            return Err(());
+        };
+        let manifest = Manifest::read(Path::new("package.json")).unwrap_or_default();
+
+        let graph = DependencyGraph::from_lockfile(&lockfile, &HashMap::new());
+        let deprecations = collect(&graph);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&deprecations).unwrap_or_default());
+            return Ok(());
+        }
+
+        if deprecations.is_empty() {
+            success("No deprecated packages", false);
+            return Ok(());
+        }
+
+        report(&deprecations, manifest.root_name());
+
+        Ok(())
+    }
+}
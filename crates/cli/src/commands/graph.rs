@@ -0,0 +1,83 @@
+use crate::Command;
+use async_trait::async_trait;
+
+use clap::Args;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use resolver::{
+    export::{Format, render},
+    graph::{DAGBuilder, DependencyGraph},
+    lockfile::{DependencyKind, LOCKFILE_NAME, Lockfile},
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct GraphCommand {
+    #[clap(long)]
+    depth: Option<usize>,
+
+    #[clap(long)]
+    focus: Option<String>,
+
+    #[clap(long, default_value_t = false)]
+    prod: bool,
+
+    #[clap(long, default_value = "json")]
+    format: String,
+}
+
+#[async_trait]
+impl Command for GraphCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let format: Format = match self.format.parse() {
+            Ok(format) => format,
+            Err(err) => {
+                error(err, false);
+                return Err(());
+            }
+        };
+
+        let Some(lockfile) = Lockfile::read(Path::new(LOCKFILE_NAME)) else {
+            error(format!("Could not read {LOCKFILE_NAME}, run 'qp lock' first"), false);
+            return Err(());
+        };
+        let mut graph = DependencyGraph::from_lockfile(&lockfile, &HashMap::new());
+
+        if self.prod {
+            let roots = lockfile
+                .dependencies
+                .values()
+                .filter(|dep| dep.kind != DependencyKind::Dev)
+                .map(|dep| dep.package.clone())
+                .collect();
+            graph = graph.subgraph(roots, None);
+        }
+
+        let roots = match &self.focus {
+            Some(focus) => {
+                let roots: Vec<String> = graph
+                    .nodes
+                    .iter()
+                    .filter(|(key, node)| *key == focus || node.info.name == *focus)
+                    .map(|(key, _)| key.clone())
+                    .collect();
+
+                if roots.is_empty() {
+                    error(format!("{focus} is not in the dependency graph"), false);
+                    return Err(());
+                }
+                roots
+            }
+            None => graph.roots.clone(),
+        };
+        graph = graph.subgraph(roots, self.depth);
+
+        DAGBuilder::new().hydrate(&mut graph).await;
+
+        println!("{}", render(&graph, format).trim_end());
+
+        Ok(())
+    }
+}
@@ -1,14 +1,104 @@
-use crate::Command;
+use crate::{
+    Command,
+    utils::{ResolveArgs, check_engines, check_failures, omitted_kinds},
+};
 use async_trait::async_trait;
 
 use clap::Args;
 
+use std::{collections::HashMap, path::Path, time::Instant};
+
+use resolver::{
+    deprecations,
+    graph::DependencyGraph,
+    lockfile::{LOCKFILE_NAME, Lockfile},
+    manifest::Manifest,
+    overrides::Overrides,
+};
+use store::Store;
+use utils::logger::*;
+
 #[derive(Debug, Args)]
-pub(crate) struct InstallCommand {}
+pub(crate) struct InstallCommand {
+    #[clap(flatten)]
+    resolve: ResolveArgs,
+
+    #[clap(long, default_value_t = false)]
+    prod: bool,
+
+    #[clap(long, value_delimiter = ',')]
+    omit: Vec<String>,
+
+    #[clap(long, value_delimiter = ',')]
+    include: Vec<String>,
+}
 
 #[async_trait]
 impl Command for InstallCommand {
     async fn run(&self) -> Result<(), ()> {
+        let start = Instant::now();
+
+        let omit = omitted_kinds(self.prod, &self.omit, &self.include)?;
+
+        let Some(manifest) = Manifest::read(Path::new("package.json")) else {
+            error("Could not read package.json", false);
+            return Err(());
+        };
+
+        let lock_path = Path::new(LOCKFILE_NAME);
+        let existing = Lockfile::read(lock_path).filter(|lockfile| {
+            lockfile.matches(&manifest)
+                && lockfile.overrides == Overrides::from_manifest(&manifest).to_map()
+        });
+
+        let flags = self.resolve.resolution_flags();
+        if existing.is_some() && !flags.is_empty() {
+            info(format!("Re-resolving {LOCKFILE_NAME} for {}", flags.join(", ")), false);
+        }
+
+        let lockfile = match existing.filter(|_| flags.is_empty()) {
+            Some(lockfile) => lockfile,
+            None => {
+                let builder = self.resolve.builder(false)?;
+                let resolution = self.resolve.report(builder.resolve_manifest(&manifest).await)?;
+
+                if resolution.lockfile.write(lock_path).is_err() {
+                    error(format!("Could not write {LOCKFILE_NAME}"), false);
+                    return Err(());
+                }
+
+                resolution.lockfile
+            }
+        };
+
+        if !omit.is_empty() {
+            let kinds: Vec<String> = omit.iter().map(ToString::to_string).collect();
+            info(format!("Omitting {} dependencies", kinds.join(", ")), false);
+        }
+
+        let packages = lockfile.package_versions_without(&omit);
+        check_engines(&packages, self.resolve.engine_strict)?;
+
+        let reachable = lockfile.reachable(&omit);
+        let graph = DependencyGraph::from_lockfile(&lockfile, &HashMap::new());
+        let mut deprecated = deprecations::collect(&graph);
+        deprecated.retain(|deprecation| reachable.contains(&deprecation.package));
+        deprecations::report(&deprecated, manifest.root_name());
+
+        let store = Store::new();
+        let (installed, failed) = store.install_packages(packages).await;
+
+        check_failures(&failed, &lockfile, &omit)?;
+
+        if installed.is_empty() {
+            success("All packages already installed", false);
+        } else {
+            success(format!("Installed {} packages", installed.len()), false);
+        }
+
+        let duration = start.elapsed();
+        success(format!("Finished in: {duration:.2?}"), false);
+
         Ok(())
     }
 }
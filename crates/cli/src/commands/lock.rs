@@ -1,14 +1,47 @@
-use crate::Command;
+use crate::{
+    Command,
+    utils::{ResolveArgs, check_engines},
+};
 use async_trait::async_trait;
 
 use clap::Args;
 
+use std::{path::Path, time::Instant};
+
+use resolver::{lockfile::LOCKFILE_NAME, manifest::Manifest};
+use utils::logger::*;
+
 #[derive(Debug, Args)]
-pub(crate) struct LockCommand {}
+pub(crate) struct LockCommand {
+    #[clap(flatten)]
+    resolve: ResolveArgs,
+}
 
 #[async_trait]
 impl Command for LockCommand {
     async fn run(&self) -> Result<(), ()> {
+        let start = Instant::now();
+
+        let Some(manifest) = Manifest::read(Path::new("package.json")) else {
+            error("Could not read package.json", false);
+            return Err(());
+        };
+
+        let builder = self.resolve.builder(false)?;
+        let resolution = self.resolve.report(builder.resolve_manifest(&manifest).await)?;
+
+        check_engines(resolution.packages.values(), self.resolve.engine_strict)?;
+
+        if resolution.lockfile.write(Path::new(LOCKFILE_NAME)).is_err() {
+            error(format!("Could not write {LOCKFILE_NAME}"), false);
+            return Err(());
+        }
+
+        success(format!("Locked {} packages", resolution.lockfile.packages.len()), false);
+
+        let duration = start.elapsed();
+        success(format!("Finished in: {duration:.2?}"), false);
+
         Ok(())
     }
 }
@@ -0,0 +1,85 @@
+use crate::{
+    Command,
+    utils::{normalize_scope, registry_url},
+};
+use async_trait::async_trait;
+
+use clap::Args;
+
+use client::{
+    auth::{nerf_dart, whoami},
+    config::{update_file, user_config_path},
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct LoginCommand {
+    #[clap(long)]
+    registry: Option<String>,
+
+    #[clap(long)]
+    scope: Option<String>,
+
+    #[clap(long)]
+    token: Option<String>,
+}
+
+#[async_trait]
+impl Command for LoginCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let scope = self.scope.as_deref().map(normalize_scope);
+        let registry = registry_url(self.registry.as_deref(), scope.as_deref());
+
+        let Some(nerf) = nerf_dart(&registry) else {
+            error(format!("Invalid registry URL: {registry}"), false);
+            return Err(());
+        };
+
+        let token = match &self.token {
+            Some(token) => token.clone(),
+            None => {
+                let mut term = Term::default();
+                let mut theme = MinimalTheme::default();
+                let mut p = Promptuity::new(&mut term, &mut theme);
+                p.begin().unwrap();
+                let token = p
+                    .prompt(
+                        Password::new(format!("Access token for {registry}")).with_required(true),
+                    )
+                    .unwrap_or_default();
+                p.finish().unwrap();
+                token
+            }
+        };
+
+        if token.trim().is_empty() {
+            error("No access token given", false);
+            return Err(());
+        }
+
+        let Some(path) = user_config_path() else {
+            error("Could not locate the home directory", false);
+            return Err(());
+        };
+
+        let mut updates = vec![(format!("{nerf}:_authToken"), Some(token.trim().to_string()))];
+        if let Some(scope) = &scope {
+            updates.push((format!("{scope}:registry"), Some(registry.clone())));
+        }
+
+        if update_file(&path, &updates).is_err() {
+            error(format!("Could not write {}", path.display()), false);
+            return Err(());
+        }
+
+        match whoami(&registry, token.trim()).await {
+            Some(username) => success(format!("Logged in to {registry} as {username}"), false),
+            None => {
+                success(format!("Saved token for {registry}"), false);
+                warn("Could not verify the token with the registry", false);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{
+    Command,
+    utils::{normalize_scope, registry_url},
+};
+use async_trait::async_trait;
+
+use clap::Args;
+
+use client::{
+    auth::{CREDENTIAL_KEYS, nerf_dart},
+    config::{Config, update_file, user_config_path},
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct LogoutCommand {
+    #[clap(long)]
+    registry: Option<String>,
+
+    #[clap(long)]
+    scope: Option<String>,
+}
+
+#[async_trait]
+impl Command for LogoutCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let scope = self.scope.as_deref().map(normalize_scope);
+        let registry = registry_url(self.registry.as_deref(), scope.as_deref());
+
+        let Some(nerf) = nerf_dart(&registry) else {
+            error(format!("Invalid registry URL: {registry}"), false);
+            return Err(());
+        };
+
+        let Some(path) = user_config_path() else {
+            error("Could not locate the home directory", false);
+            return Err(());
+        };
+
+        let keys: Vec<String> = CREDENTIAL_KEYS.iter().map(|key| format!("{nerf}:{key}")).collect();
+
+        let mut config = Config::default();
+        config.merge_file(&path);
+        if keys.iter().all(|key| config.get(key).is_none()) {
+            warn(format!("Not logged in to {registry}"), false);
+            return Ok(());
+        }
+
+        let updates: Vec<(String, Option<String>)> =
+            keys.into_iter().map(|key| (key, None)).collect();
+        if update_file(&path, &updates).is_err() {
+            error(format!("Could not write {}", path.display()), false);
+            return Err(());
+        }
+
+        success(format!("Logged out of {registry}"), false);
+
+        Ok(())
+    }
+}
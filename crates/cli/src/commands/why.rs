@@ -0,0 +1,82 @@
+use crate::Command;
+use async_trait::async_trait;
+
+use clap::Args;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use resolver::{
+    graph::DependencyGraph,
+    lockfile::{LOCKFILE_NAME, Lockfile, package_name, package_version},
+    manifest::Manifest,
+    why::{Step, explain},
+};
+use utils::logger::*;
+
+#[derive(Debug, Args)]
+pub(crate) struct WhyCommand {
+    package: String,
+
+    #[clap(long, default_value_t = false)]
+    json: bool,
+}
+
+fn format_step(step: &Step) -> String {
+    let resolved = package_name(&step.package);
+    let version = package_version(&step.package);
+
+    let requested = match &step.overridden {
+        Some(applied) => format!(
+            "{}@{} → {} [override {}]",
+            step.name, step.specifier, applied.specifier, applied.selector
+        ),
+        None => format!("{}@{}", step.name, step.specifier),
+    };
+
+    match resolved == step.name {
+        true => format!("{requested} ({version})"),
+        false => format!("{requested} ({resolved}@{version})"),
+    }
+}
+
+#[async_trait]
+impl Command for WhyCommand {
+    async fn run(&self) -> Result<(), ()> {
+        let Some(lockfile) = Lockfile::read(Path::new(LOCKFILE_NAME)) else {
+            error(format!("Could not read {LOCKFILE_NAME}, run 'qp lock' first"), false);
+            return Err(());
+        };
+        let manifest = Manifest::read(Path::new("package.json")).unwrap_or_default();
+
+        let graph = DependencyGraph::from_lockfile(&lockfile, &HashMap::new());
+        let explanations = explain(&graph, &self.package);
+
+        if explanations.is_empty() {
+            error(format!("{} is not in the dependency graph", self.package), false);
+            return Err(());
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&explanations).unwrap_or_default());
+            return Ok(());
+        }
+
+        for explanation in &explanations {
+            let paths = explanation.chains.len();
+            let noun = if paths == 1 { "path" } else { "paths" };
+            info(format!("{} ({paths} {noun})", explanation.package), false);
+
+            for chain in &explanation.chains {
+                let hops: Vec<String> = chain.iter().map(format_step).collect();
+                sub_info(format!("{} › {}", manifest.root_name(), hops.join(" › ")), false);
+            }
+
+            if explanation.truncated {
+                sub_warn("more paths omitted", false);
+            }
+        }
+
+        Ok(())
+    }
+}
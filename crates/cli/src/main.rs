@@ -4,6 +4,7 @@ mod utils;
 
 use async_trait::async_trait;
 use clap::{Parser, Subcommand};
+use client::cache::{NetworkMode, set_network_mode};
 use commands::*;
 
 #[derive(Parser)]
@@ -13,6 +14,12 @@ use commands::*;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    #[clap(long, global = true, default_value_t = false)]
+    prefer_offline: bool,
+
+    #[clap(long, global = true, default_value_t = false)]
+    offline: bool,
 }
 
 #[derive(Subcommand)]
@@ -29,6 +36,12 @@ enum Commands {
     Lock(LockCommand),
     List(ListCommand),
     Store(StoreCommand),
+    Dedupe(DedupeCommand),
+    Deprecations(DeprecationsCommand),
+    Graph(GraphCommand),
+    Why(WhyCommand),
+    Login(LoginCommand),
+    Logout(LogoutCommand),
 }
 
 #[async_trait]
@@ -47,6 +60,12 @@ impl Command for Commands {
             Commands::Lock(cmd) => cmd.run().await?,
             Commands::List(cmd) => cmd.run().await?,
             Commands::Store(cmd) => cmd.run().await?,
+            Commands::Dedupe(cmd) => cmd.run().await?,
+            Commands::Deprecations(cmd) => cmd.run().await?,
+            Commands::Graph(cmd) => cmd.run().await?,
+            Commands::Why(cmd) => cmd.run().await?,
+            Commands::Login(cmd) => cmd.run().await?,
+            Commands::Logout(cmd) => cmd.run().await?,
         }
 
         Ok(())
@@ -57,9 +76,15 @@ impl Command for Commands {
 async fn main() {
     let cli = Cli::parse();
 
-    if let Some(cmd) = cli.command {
-        if cmd.run().await.is_err() {
-            std::process::exit(1);
-        }
+    if cli.offline {
+        set_network_mode(NetworkMode::Offline);
+    } else if cli.prefer_offline {
+        set_network_mode(NetworkMode::PreferOffline);
+    }
+
+    if let Some(cmd) = cli.command
+        && cmd.run().await.is_err()
+    {
+        std::process::exit(1);
     }
 }
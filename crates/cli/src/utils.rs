@@ -1,26 +1,229 @@
-use client::versions::RequestPackage;
+use clap::Args;
+use client::{
+    config::CONFIG,
+    registry::{PackageVersion, parse_timestamp, registry_for},
+    versions::RequestPackage,
+};
+use resolver::{
+    dedupe, engines,
+    graph::{DAGBuilder, Resolution},
+    lockfile::{DependencyKind, Lockfile},
+    peers,
+    platform::Platform,
+    semver::{SelectOptions, SemVer},
+    solver::Conflict,
+    specifier::{Specifier, split_name_spec},
+};
+use store::store_key;
+use utils::logger::*;
+
+use std::collections::HashSet;
+
+#[derive(Debug, Args)]
+pub struct ResolveArgs {
+    #[clap(long)]
+    pub before: Option<String>,
+
+    #[clap(long)]
+    pub os: Option<String>,
+
+    #[clap(long)]
+    pub cpu: Option<String>,
+
+    #[clap(long, default_value_t = false)]
+    pub strict_peer_dependencies: bool,
+
+    #[clap(long, default_value_t = false)]
+    pub engine_strict: bool,
+
+    #[clap(long, default_value_t = false)]
+    pub prefer_engine_compatible: bool,
+}
+
+impl ResolveArgs {
+    pub fn builder(&self, include_prerelease: bool) -> Result<DAGBuilder, ()> {
+        let mut options = select_options(self.before.as_deref(), self.prefer_engine_compatible)?;
+        options.include_prerelease = include_prerelease;
+        let platform = Platform::target(self.os.clone(), self.cpu.clone());
+        Ok(DAGBuilder::with_options(options).with_platform(platform))
+    }
+
+    pub fn resolution_flags(&self) -> Vec<&'static str> {
+        [
+            ("--before", self.before.is_some()),
+            ("--os", self.os.is_some()),
+            ("--cpu", self.cpu.is_some()),
+            ("--prefer-engine-compatible", self.prefer_engine_compatible),
+        ]
+        .into_iter()
+        .filter_map(|(flag, given)| given.then_some(flag))
+        .collect()
+    }
+
+    pub fn report(&self, result: Result<Resolution, Conflict>) -> Result<Resolution, ()> {
+        let resolution = result.map_err(|conflict| error(conflict.to_string(), false))?;
+
+        dedupe::report(&resolution.merges);
+        peers::report(&resolution.peer_issues, self.strict_peer_dependencies);
+        if self.strict_peer_dependencies && !resolution.peer_issues.is_empty() {
+            return Err(());
+        }
+
+        Ok(resolution)
+    }
+}
 
 pub fn parse_package_str(package: String) -> RequestPackage {
-    if package.starts_with('@') {
-        if let Some(pos) = package.rfind('@') {
-            if pos == 0 {
-                return RequestPackage { name: package, version: None };
-            } else {
-                let name = &package[..pos];
-                let version = &package[pos + 1..];
-                return RequestPackage {
-                    name: name.to_string(),
-                    version: Some(version.to_string()),
-                };
-            }
-        } else {
-            return RequestPackage { name: package, version: None };
+    if let Some(spec) = Specifier::parse(&package)
+        && !spec.is_registry()
+    {
+        return RequestPackage { name: String::new(), version: Some(package) };
+    }
+
+    let (name, version) = split_name_spec(&package);
+    let version = if package.len() > name.len() { Some(version.to_string()) } else { None };
+
+    RequestPackage { name: name.to_string(), version }
+}
+
+fn select_options(
+    before: Option<&str>,
+    prefer_engine_compatible: bool,
+) -> Result<SelectOptions, ()> {
+    let mut options = SelectOptions::from_config();
+
+    if prefer_engine_compatible {
+        options.node = engines::node_version();
+        if options.node.is_none() {
+            warn("Could not detect the node version, ignoring --prefer-engine-compatible", false);
+        }
+    }
+
+    if let Some(before) = before {
+        let Some(before) = parse_timestamp(before) else {
+            error(format!("Invalid date for --before: {before}"), false);
+            return Err(());
+        };
+        options.before = Some(before);
+    }
+
+    Ok(options)
+}
+
+pub fn check_engines<'a>(
+    packages: impl IntoIterator<Item = &'a PackageVersion>,
+    engine_strict: bool,
+) -> Result<(), ()> {
+    let strict = engine_strict || CONFIG.get_bool("engine-strict").unwrap_or(false);
+    let packages: Vec<&PackageVersion> = packages.into_iter().collect();
+
+    let node = engines::node_version();
+    if node.is_none() {
+        if strict {
+            error("Could not detect the node version required by --engine-strict", false);
+            return Err(());
+        }
+        debug("Could not detect the node version, skipping node engine checks", false);
+    }
+
+    let wants_npm = packages.iter().any(|info| engines::requires(info, "npm"));
+    let npm = wants_npm.then(engines::npm_version).flatten();
+    if wants_npm && npm.is_none() {
+        debug("Could not detect the npm version, skipping npm engine checks", false);
+    }
+
+    let issues = engines::check(packages, node.as_ref(), npm.as_ref());
+    let versions: Vec<(&str, &SemVer)> = [("node", node.as_ref()), ("npm", npm.as_ref())]
+        .into_iter()
+        .filter_map(|(engine, version)| Some((engine, version?)))
+        .collect();
+    engines::report(&issues, &versions, strict);
+
+    match strict && !issues.is_empty() {
+        true => Err(()),
+        false => Ok(()),
+    }
+}
+
+pub fn check_failures(
+    failed: &[String],
+    lockfile: &Lockfile,
+    omit: &[DependencyKind],
+) -> Result<(), ()> {
+    let mut omit = omit.to_vec();
+    omit.push(DependencyKind::Optional);
+    let required: HashSet<String> =
+        lockfile.package_versions_without(&omit).iter().map(store_key).collect();
+
+    let (required, optional): (Vec<&String>, Vec<&String>) =
+        failed.iter().partition(|key| required.contains(*key));
+
+    for key in optional {
+        warn(format!("Skipping optional dependency {key}"), false);
+    }
+
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let keys: Vec<&str> = required.into_iter().map(String::as_str).collect();
+    error(format!("Failed to install {}", keys.join(", ")), false);
+    Err(())
+}
+
+pub fn omitted_kinds(
+    prod: bool,
+    omit: &[String],
+    include: &[String],
+) -> Result<Vec<DependencyKind>, ()> {
+    let parse = |values: &[String]| -> Result<Vec<DependencyKind>, ()> {
+        values
+            .iter()
+            .flat_map(|value| value.split(','))
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| match value.parse() {
+                Ok(DependencyKind::Prod) => {
+                    error("prod dependencies cannot be omitted", false);
+                    Err(())
+                }
+                Ok(kind) => Ok(kind),
+                Err(err) => {
+                    error(err, false);
+                    Err(())
+                }
+            })
+            .collect()
+    };
+
+    let mut omitted = match omit.is_empty() {
+        true => {
+            parse(&CONFIG.get("omit").map(|value| vec![value.to_string()]).unwrap_or_default())?
         }
+        false => parse(omit)?,
+    };
+    if prod || (omit.is_empty() && std::env::var("NODE_ENV").is_ok_and(|env| env == "production")) {
+        omitted.push(DependencyKind::Dev);
     }
 
-    let parts: Vec<&str> = package.splitn(2, '@').collect();
-    let name = parts[0].to_string();
-    let version = if parts.len() > 1 { Some(parts[1].to_string()) } else { None };
+    let included = parse(include)?;
+    omitted.retain(|kind| !included.contains(kind));
+    omitted.sort_by_key(|kind| kind.to_string());
+    omitted.dedup();
+
+    Ok(omitted)
+}
 
-    RequestPackage { name, version }
+pub fn registry_url(registry: Option<&str>, scope: Option<&str>) -> String {
+    match (registry, scope) {
+        (Some(registry), _) => format!("{}/", registry.trim_end_matches('/')),
+        (None, Some(scope)) => registry_for(&format!("{scope}/")),
+        (None, None) => registry_for(""),
+    }
+}
+
+pub fn normalize_scope(scope: &str) -> String {
+    match scope.starts_with('@') {
+        true => scope.trim_end_matches('/').to_string(),
+        false => format!("@{}", scope.trim_end_matches('/')),
+    }
 }
@@ -0,0 +1,165 @@
+use crate::config::{CONFIG, Config};
+use crate::http;
+use crate::registry::DEFAULT_REGISTRY;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::{RequestBuilder, header::AUTHORIZATION};
+use serde::Deserialize;
+
+pub const CREDENTIAL_KEYS: [&str; 4] = ["_authToken", "_auth", "username", "_password"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credentials {
+    Token(String),
+    Basic(String),
+}
+
+impl Credentials {
+    fn header(&self) -> String {
+        match self {
+            Self::Token(token) => format!("Bearer {token}"),
+            Self::Basic(encoded) => format!("Basic {encoded}"),
+        }
+    }
+}
+
+pub fn nerf_dart(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let rest = rest.split(['?', '#']).next()?;
+
+    match rest.rfind('/') {
+        Some(pos) => Some(format!("//{}", &rest[..=pos])),
+        None => Some(format!("//{rest}/")),
+    }
+}
+
+pub fn credentials_for(url: &str) -> Option<Credentials> {
+    credentials_in(&CONFIG, url)
+}
+
+fn credentials_in(config: &Config, url: &str) -> Option<Credentials> {
+    let mut prefix = nerf_dart(url)?;
+
+    loop {
+        if let Some(credentials) = scoped_credentials(config, &prefix) {
+            return Some(credentials);
+        }
+
+        let trimmed = prefix.trim_end_matches('/');
+        match trimmed.rfind('/') {
+            Some(pos) if pos > 1 => prefix.truncate(pos + 1),
+            _ => break,
+        }
+    }
+
+    let registry = config.get("registry").unwrap_or(DEFAULT_REGISTRY).trim_end_matches('/');
+    if !url.starts_with(&format!("{registry}/")) {
+        return None;
+    }
+
+    config
+        .get("_authToken")
+        .map(|token| Credentials::Token(token.to_string()))
+        .or_else(|| config.get("_auth").map(|auth| Credentials::Basic(auth.to_string())))
+}
+
+fn scoped_credentials(config: &Config, prefix: &str) -> Option<Credentials> {
+    let get = |key: &str| config.get(&format!("{prefix}:{key}")).filter(|v| !v.is_empty());
+
+    if let Some(token) = get("_authToken") {
+        return Some(Credentials::Token(token.to_string()));
+    }
+    if let Some(auth) = get("_auth") {
+        return Some(Credentials::Basic(auth.to_string()));
+    }
+
+    let username = get("username")?;
+    let password = get("_password")?;
+    let password = STANDARD
+        .decode(password)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| password.to_string());
+
+    Some(Credentials::Basic(STANDARD.encode(format!("{username}:{password}"))))
+}
+
+pub fn authorize(request: RequestBuilder, url: &str) -> RequestBuilder {
+    match credentials_for(url) {
+        Some(credentials) => request.header(AUTHORIZATION, credentials.header()),
+        None => request,
+    }
+}
+
+#[derive(Deserialize)]
+struct WhoAmI {
+    username: String,
+}
+
+pub async fn whoami(registry: &str, token: &str) -> Option<String> {
+    let url = format!("{}/-/whoami", registry.trim_end_matches('/'));
+    let response =
+        http::build(http::client_builder()).get(url).bearer_auth(token).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+
+    response.json::<WhoAmI>().await.ok().map(|user| user.username)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(value: &str) -> Option<Credentials> {
+        Some(Credentials::Token(value.to_string()))
+    }
+
+    #[test]
+    fn nerf_dart_keeps_host_and_path() {
+        assert_eq!(nerf_dart("https://r.example/npm/pkg").as_deref(), Some("//r.example/npm/"));
+        assert_eq!(nerf_dart("https://r.example").as_deref(), Some("//r.example/"));
+        assert_eq!(nerf_dart("https://r.example/a/?x=/y").as_deref(), Some("//r.example/a/"));
+    }
+
+    #[test]
+    fn tokens_require_matching_host_and_path() {
+        let config = Config::from_ini("//r.example/npm/:_authToken=scoped");
+
+        assert_eq!(credentials_in(&config, "https://r.example/npm/pkg"), token("scoped"));
+        assert_eq!(credentials_in(&config, "https://r.example/npm/@s%2fpkg"), token("scoped"));
+        assert_eq!(credentials_in(&config, "https://r.example/other/pkg"), None);
+        assert_eq!(credentials_in(&config, "https://r.example/npmx/pkg"), None);
+        assert_eq!(credentials_in(&config, "https://r.example.evil/npm/pkg"), None);
+        assert_eq!(credentials_in(&config, "https://evil.example/r.example/npm/pkg"), None);
+    }
+
+    #[test]
+    fn scoped_tokens_stay_on_their_registry() {
+        let config = Config::from_ini(
+            "@acme:registry=https://npm.acme.test/\n//npm.acme.test/:_authToken=acme",
+        );
+
+        assert_eq!(credentials_in(&config, "https://npm.acme.test/@acme%2fui"), token("acme"));
+        assert_eq!(
+            credentials_in(&config, "https://npm.acme.test/@acme/ui/-/ui-1.0.0.tgz"),
+            token("acme")
+        );
+        assert_eq!(credentials_in(&config, "https://cdn.acme.test/ui/-/ui-1.0.0.tgz"), None);
+        assert_eq!(credentials_in(&config, "https://registry.npmjs.org/@acme%2fui"), None);
+    }
+
+    #[test]
+    fn top_level_tokens_only_reach_the_default_registry() {
+        let config = Config::from_ini("_authToken=global");
+
+        assert_eq!(credentials_in(&config, "https://registry.npmjs.org/pkg"), token("global"));
+        assert_eq!(credentials_in(&config, "https://registry.npmjs.org.evil/pkg"), None);
+        assert_eq!(credentials_in(&config, "https://other.example/pkg"), None);
+
+        let config = Config::from_ini("registry=https://r.example/npm/\n_auth=dXNlcjpwYXNz");
+        let basic = Some(Credentials::Basic("dXNlcjpwYXNz".to_string()));
+        assert_eq!(credentials_in(&config, "https://r.example/npm/pkg"), basic);
+        assert_eq!(credentials_in(&config, "https://registry.npmjs.org/pkg"), None);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::config::CONFIG;
+use crate::registry::{MetadataKind, RegistryPackage, registry_for};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use utils::logger::*;
+
+const CACHE_FORMAT: &[u8] = b"qpc1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkMode {
+    Online,
+    PreferOffline,
+    Offline,
+}
+
+static NETWORK_MODE: Lazy<AtomicU8> = Lazy::new(|| {
+    let mode = if CONFIG.get_bool("offline").unwrap_or(false) {
+        NetworkMode::Offline
+    } else if CONFIG.get_bool("prefer-offline").unwrap_or(false) {
+        NetworkMode::PreferOffline
+    } else {
+        NetworkMode::Online
+    };
+    AtomicU8::new(mode as u8)
+});
+
+pub fn set_network_mode(mode: NetworkMode) {
+    NETWORK_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn network_mode() -> NetworkMode {
+    match NETWORK_MODE.load(Ordering::Relaxed) {
+        2 => NetworkMode::Offline,
+        1 => NetworkMode::PreferOffline,
+        _ => NetworkMode::Online,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedPackument {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub package: RegistryPackage,
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".qipi").join("cache").join("metadata"))
+}
+
+fn entry_path(name: &str, kind: MetadataKind) -> Option<PathBuf> {
+    let registry = registry_for(name);
+    let host = registry.split("://").nth(1).unwrap_or(&registry).trim_end_matches('/');
+    let name = name.replace('/', "+");
+    let file = match kind {
+        MetadataKind::Abbreviated => format!("{name}.bin"),
+        MetadataKind::Full => format!("{name}.full.bin"),
+    };
+    Some(cache_dir()?.join(host.replace(['/', ':'], "+")).join(file))
+}
+
+pub async fn read(name: &str, kind: MetadataKind) -> Option<CachedPackument> {
+    let bytes = tokio::fs::read(entry_path(name, kind)?).await.ok()?;
+    let body = bytes.strip_prefix(CACHE_FORMAT)?;
+
+    match rmp_serde::from_slice(body) {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            debug(format!("discarding cached metadata for {name}: {err}"), false);
+            None
+        }
+    }
+}
+
+pub async fn write(name: &str, kind: MetadataKind, entry: &CachedPackument) {
+    let Some(path) = entry_path(name, kind) else {
+        return;
+    };
+    let Ok(body) = rmp_serde::to_vec(entry) else {
+        return;
+    };
+
+    let mut bytes = CACHE_FORMAT.to_vec();
+    bytes.extend(body);
+
+    if let Some(dir) = path.parent() {
+        let _ = tokio::fs::create_dir_all(dir).await;
+    }
+
+    let tmp = path.with_extension("tmp");
+    if tokio::fs::write(&tmp, bytes).await.is_err() || tokio::fs::rename(&tmp, &path).await.is_err()
+    {
+        debug(format!("could not write cached metadata for {name}"), false);
+    }
+}
@@ -0,0 +1,195 @@
+use once_cell::sync::Lazy;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{OpenOptions, read_to_string};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use utils::logger::*;
+
+pub const CONFIG_FILE_NAME: &str = ".qipirc";
+pub const NPMRC_FILE_NAME: &str = ".npmrc";
+
+pub static CONFIG: Lazy<Config> = Lazy::new(Config::load);
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    values: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let mut config = Self::default();
+
+        for path in Self::paths() {
+            config.merge_file(&path);
+        }
+
+        config
+    }
+
+    pub fn paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            paths.push(home.join(NPMRC_FILE_NAME));
+            paths.push(home.join(CONFIG_FILE_NAME));
+        }
+        paths.push(PathBuf::from(NPMRC_FILE_NAME));
+        paths.push(PathBuf::from(CONFIG_FILE_NAME));
+        paths
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_ini(text: &str) -> Self {
+        let mut config = Self::default();
+        config.merge_text(text);
+        config
+    }
+
+    pub fn merge_file(&mut self, path: &Path) {
+        if let Ok(text) = read_to_string(path) {
+            self.merge_text(&text);
+        }
+    }
+
+    fn merge_text(&mut self, text: &str) {
+        let entries = parse_ini(text);
+        self.values.extend(entries.into_iter().map(|(k, v)| (expand_env(&k), expand_env(&v))));
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" | "1" | "yes" => Some(true),
+            "false" | "0" | "no" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(CONFIG_FILE_NAME))
+}
+
+pub fn update_file(path: &Path, updates: &[(String, Option<String>)]) -> io::Result<()> {
+    let text = read_to_string(path).unwrap_or_default();
+    let mut written = vec![false; updates.len()];
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let key = line.split_once('=').map(|(key, _)| key.trim());
+        let Some(index) = updates.iter().position(|(k, _)| Some(k.as_str()) == key) else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        if let (key, Some(value)) = &updates[index]
+            && !written[index]
+        {
+            lines.push(format!("{key}={value}"));
+        }
+        written[index] = true;
+    }
+
+    for ((key, value), written) in updates.iter().zip(written) {
+        if let Some(value) = value
+            && !written
+        {
+            lines.push(format!("{key}={value}"));
+        }
+    }
+
+    let mut data = lines.join("\n");
+    data.push('\n');
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(data.as_bytes())
+}
+
+fn expand_env(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+
+        out.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + end];
+        match env::var(name) {
+            Ok(value) => out.push_str(&value),
+            Err(_) => warn(format!("environment variable {name} is not set"), false),
+        }
+        rest = &rest[start + end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+pub fn parse_ini(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', ';', '[']))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_file_replaces_and_removes_keys() {
+        let path = env::temp_dir().join(format!("qipirc-update-{}", std::process::id()));
+        std::fs::write(&path, "color=true\n//r.example/:_authToken=old\nemail=a@b.c\n").unwrap();
+
+        let updates = [
+            ("//r.example/:_authToken".to_string(), Some("new".to_string())),
+            ("email".to_string(), None),
+            ("registry".to_string(), Some("https://r.example/".to_string())),
+        ];
+        update_file(&path, &updates).unwrap();
+
+        let text = read_to_string(&path).unwrap();
+        assert_eq!(text, "color=true\n//r.example/:_authToken=new\nregistry=https://r.example/\n");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn update_file_keeps_credentials_private() {
+        let path = env::temp_dir().join(format!("qipirc-mode-{}", std::process::id()));
+        std::fs::write(&path, "color=true\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        update_file(&path, &[("_authToken".to_string(), Some("secret".to_string()))]).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let _ = std::fs::remove_file(&path);
+    }
+}
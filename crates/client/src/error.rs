@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum ClientError {
+    NotFound { url: String },
+    Unauthorized { url: String, status: u16 },
+    RateLimited { url: String, retry_after: Option<Duration> },
+    Unavailable { url: String, status: u16, retry_after: Option<Duration> },
+    Status { url: String, status: u16 },
+    Timeout { url: String },
+    Network { url: String, message: String },
+    Decode { url: String, message: String },
+    Integrity { url: String, expected: String, actual: String },
+    Offline { name: String },
+}
+
+impl ClientError {
+    pub fn from_reqwest(url: &str, err: reqwest::Error) -> Self {
+        let url = url.to_string();
+        if err.is_timeout() {
+            Self::Timeout { url }
+        } else if err.is_decode() {
+            Self::Decode { url, message: err.to_string() }
+        } else {
+            Self::Network { url, message: err.to_string() }
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. }
+                | Self::Unavailable { .. }
+                | Self::Timeout { .. }
+                | Self::Network { .. }
+        )
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } | Self::Unavailable { retry_after, .. } => {
+                *retry_after
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { url } => write!(f, "{url} was not found"),
+            Self::Unauthorized { url, status } => {
+                write!(f, "{url} requires valid credentials ({status})")
+            }
+            Self::RateLimited { url, .. } => write!(f, "{url} is rate limited (429)"),
+            Self::Unavailable { url, status, .. } | Self::Status { url, status } => {
+                write!(f, "{url} returned {status}")
+            }
+            Self::Timeout { url } => write!(f, "request to {url} timed out"),
+            Self::Network { url, message } => write!(f, "request to {url} failed: {message}"),
+            Self::Decode { url, message } => {
+                write!(f, "could not decode response from {url}: {message}")
+            }
+            Self::Integrity { url, expected, actual } => {
+                write!(f, "integrity check failed for {url} (expected {expected}, got {actual})")
+            }
+            Self::Offline { name } => write!(f, "{name} is not in the offline cache"),
+        }
+    }
+}
+
+impl Error for ClientError {}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::fs::{remove_dir_all, rename};
+use tokio::process::Command;
+
+use utils::logger::*;
+
+pub fn cache_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_default().join(".qipi").join("cache").join("git")
+}
+
+async fn run_git(args: &[&str], cwd: Option<&Path>) -> Option<String> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+
+    let output = match command.output().await {
+        Ok(output) => output,
+        Err(err) => {
+            error(format!("failed to run git: {err}"), false);
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug(format!("git {} failed: {}", args.join(" "), stderr.trim()), false);
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub async fn ls_remote(url: &str) -> Option<HashMap<String, String>> {
+    let output = run_git(&["ls-remote", "--", url], None).await?;
+    let mut refs = HashMap::new();
+    let mut peeled = HashMap::new();
+
+    for line in output.lines() {
+        let Some((sha, name)) = line.split_once('\t') else {
+            continue;
+        };
+
+        match name.strip_suffix("^{}") {
+            Some(tag) => peeled.insert(tag.to_string(), sha.to_string()),
+            None => refs.insert(name.to_string(), sha.to_string()),
+        };
+    }
+
+    refs.extend(peeled);
+    Some(refs)
+}
+
+pub async fn checkout(url: &str, rev: &str) -> Option<(String, PathBuf)> {
+    let cache = cache_dir();
+    tokio::fs::create_dir_all(&cache).await.ok()?;
+
+    if is_full_sha(rev) {
+        let dest = cache.join(rev);
+        if dest.exists() {
+            return Some((rev.to_string(), dest));
+        }
+    }
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let tmp = cache.join(format!(".tmp-{}-{nanos}", std::process::id()));
+    let tmp_str = tmp.to_string_lossy().to_string();
+
+    if run_git(&["clone", "--quiet", "--no-checkout", "--", url, &tmp_str], None).await.is_none() {
+        error(format!("failed to clone {url}"), false);
+        let _ = remove_dir_all(&tmp).await;
+        return None;
+    }
+
+    let spec = format!("{rev}^{{commit}}");
+    let Some(sha) = run_git(&["rev-parse", "--verify", "--quiet", &spec], Some(&tmp)).await else {
+        error(format!("could not find {rev} in {url}"), false);
+        let _ = remove_dir_all(&tmp).await;
+        return None;
+    };
+    let sha = sha.trim().to_string();
+
+    let dest = cache.join(&sha);
+    if dest.exists() {
+        let _ = remove_dir_all(&tmp).await;
+        return Some((sha, dest));
+    }
+
+    if run_git(&["checkout", "--quiet", &sha], Some(&tmp)).await.is_none() {
+        error(format!("failed to check out {sha} from {url}"), false);
+        let _ = remove_dir_all(&tmp).await;
+        return None;
+    }
+
+    let _ = remove_dir_all(tmp.join(".git")).await;
+    if rename(&tmp, &dest).await.is_err() {
+        let _ = remove_dir_all(&tmp).await;
+        if !dest.exists() {
+            return None;
+        }
+    }
+
+    Some((sha, dest))
+}
+
+pub fn is_full_sha(rev: &str) -> bool {
+    rev.len() == 40 && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -0,0 +1,152 @@
+use crate::config::CONFIG;
+
+use once_cell::sync::Lazy;
+use reqwest::{Certificate, Client, ClientBuilder, Identity, NoProxy, Proxy};
+
+use std::env;
+use std::fs::read;
+use std::time::Duration;
+
+use utils::logger::*;
+
+static SETTINGS: Lazy<NetworkSettings> = Lazy::new(NetworkSettings::from_config);
+
+struct NetworkSettings {
+    proxies: Vec<Proxy>,
+    certificates: Vec<Certificate>,
+    identity: Option<Identity>,
+    strict_ssl: bool,
+}
+
+impl NetworkSettings {
+    fn from_config() -> Self {
+        let no_proxy = setting("noproxy", &["NO_PROXY", "no_proxy"])
+            .and_then(|list| NoProxy::from_string(&list));
+        let https_proxy =
+            setting("https-proxy", &["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"])
+                .or_else(|| config_value("proxy"));
+        let http_proxy = setting("proxy", &["HTTP_PROXY", "http_proxy"]);
+
+        let proxies = [(https_proxy, "https"), (http_proxy, "http")]
+            .into_iter()
+            .filter_map(|(url, scheme)| {
+                let url = url?;
+                let proxy = match scheme {
+                    "https" => Proxy::https(&url),
+                    _ => Proxy::http(&url),
+                };
+                match proxy {
+                    Ok(proxy) => Some(proxy.no_proxy(no_proxy.clone())),
+                    Err(err) => {
+                        warn(format!("ignoring invalid proxy {url}: {err}"), false);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            proxies,
+            certificates: certificates(),
+            identity: identity(),
+            strict_ssl: CONFIG.get_bool("strict-ssl").unwrap_or(true),
+        }
+    }
+}
+
+fn config_value(key: &str) -> Option<String> {
+    CONFIG
+        .get(key)
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && *value != "null" && *value != "false")
+        .map(str::to_string)
+}
+
+fn env_value(names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+}
+
+fn setting(key: &str, env_names: &[&str]) -> Option<String> {
+    config_value(key).or_else(|| env_value(env_names))
+}
+
+fn pem(inline: &str, file: &str) -> Option<Vec<u8>> {
+    if let Some(value) = config_value(inline) {
+        return Some(value.replace("\\n", "\n").into_bytes());
+    }
+
+    let path = config_value(file)?;
+    match read(&path) {
+        Ok(bytes) => Some(bytes),
+        Err(err) => {
+            warn(format!("could not read {file} {path}: {err}"), false);
+            None
+        }
+    }
+}
+
+fn certificates() -> Vec<Certificate> {
+    let Some(bundle) = pem("ca", "cafile") else {
+        return Vec::new();
+    };
+
+    match Certificate::from_pem_bundle(&bundle) {
+        Ok(certificates) => certificates,
+        Err(err) => {
+            warn(format!("ignoring invalid CA certificates: {err}"), false);
+            Vec::new()
+        }
+    }
+}
+
+fn identity() -> Option<Identity> {
+    let cert = pem("cert", "certfile");
+    let key = pem("key", "keyfile");
+
+    let (cert, key) = match (cert, key) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) => return None,
+        _ => {
+            warn("client certificates need both a cert and a key, ignoring them", false);
+            return None;
+        }
+    };
+
+    match Identity::from_pkcs8_pem(&cert, &key) {
+        Ok(identity) => Some(identity),
+        Err(err) => {
+            warn(format!("ignoring invalid client certificate: {err}"), false);
+            None
+        }
+    }
+}
+
+pub fn client_builder() -> ClientBuilder {
+    let settings = &*SETTINGS;
+
+    let mut builder = Client::builder()
+        .tcp_keepalive(Duration::from_secs(60))
+        .connect_timeout(Duration::from_secs(5));
+
+    for proxy in &settings.proxies {
+        builder = builder.proxy(proxy.clone());
+    }
+    for certificate in &settings.certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    if let Some(identity) = &settings.identity {
+        builder = builder.identity(identity.clone());
+    }
+    if !settings.strict_ssl {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+}
+
+pub fn build(builder: ClientBuilder) -> Client {
+    builder.build().unwrap_or_else(|err| {
+        warn(format!("could not configure http client: {err}"), false);
+        Client::new()
+    })
+}
@@ -0,0 +1,144 @@
+use crate::registry::DistInfo;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            "sha384" => Some(Self::Sha384),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Integrity {
+    hashes: Vec<(Algorithm, String)>,
+}
+
+impl Integrity {
+    pub fn parse(sri: &str) -> Option<Self> {
+        let hashes: Vec<_> = sri
+            .split_whitespace()
+            .filter_map(|entry| {
+                let (algorithm, rest) = entry.split_once('-')?;
+                let digest = rest.split('?').next()?;
+                Some((Algorithm::parse(algorithm)?, digest.to_string()))
+            })
+            .collect();
+
+        (!hashes.is_empty()).then_some(Self { hashes })
+    }
+
+    pub fn from_shasum(shasum: &str) -> Option<Self> {
+        let bytes = decode_hex(shasum.trim())?;
+        (bytes.len() == 20)
+            .then(|| Self { hashes: vec![(Algorithm::Sha1, STANDARD.encode(bytes))] })
+    }
+
+    pub fn for_dist(dist: &DistInfo) -> Option<Self> {
+        dist.integrity.as_deref().and_then(Self::parse).or_else(|| Self::from_shasum(&dist.shasum))
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.hashes.iter().map(|(algorithm, _)| *algorithm).max().unwrap_or(Algorithm::Sha512)
+    }
+
+    pub fn hasher(&self) -> IntegrityHasher {
+        IntegrityHasher::new(self.algorithm())
+    }
+
+    pub fn matches(&self, actual: &Integrity) -> bool {
+        let algorithm = self.algorithm();
+        self.hashes.iter().filter(|(a, _)| *a == algorithm).any(|hash| actual.hashes.contains(hash))
+    }
+}
+
+impl fmt::Display for Integrity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (algorithm, digest)) in self.hashes.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{algorithm}-{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+pub enum IntegrityHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+
+impl IntegrityHasher {
+    pub fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha1 => Self::Sha1(Sha1::new()),
+            Algorithm::Sha256 => Self::Sha256(Sha256::new()),
+            Algorithm::Sha384 => Self::Sha384(Sha384::new()),
+            Algorithm::Sha512 => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha1(hasher) => hasher.update(bytes),
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Sha384(hasher) => hasher.update(bytes),
+            Self::Sha512(hasher) => hasher.update(bytes),
+        }
+    }
+
+    pub fn finish(self) -> Integrity {
+        let (algorithm, digest) = match self {
+            Self::Sha1(hasher) => (Algorithm::Sha1, hasher.finalize().to_vec()),
+            Self::Sha256(hasher) => (Algorithm::Sha256, hasher.finalize().to_vec()),
+            Self::Sha384(hasher) => (Algorithm::Sha384, hasher.finalize().to_vec()),
+            Self::Sha512(hasher) => (Algorithm::Sha512, hasher.finalize().to_vec()),
+        };
+        Integrity { hashes: vec![(algorithm, STANDARD.encode(digest))] }
+    }
+}
+
+pub fn compute(bytes: &[u8]) -> Integrity {
+    let mut hasher = IntegrityHasher::new(Algorithm::Sha512);
+    hasher.update(bytes);
+    hasher.finish()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
@@ -1,2 +1,11 @@
+pub mod auth;
+pub mod cache;
+pub mod config;
+pub mod error;
+pub mod git;
+pub mod http;
+pub mod integrity;
 pub mod registry;
+pub mod retry;
+pub mod tarball;
 pub mod versions;
@@ -1,21 +1,39 @@
-use serde::Deserialize;
+use crate::config::{CONFIG, Config};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Deserialize, Clone)]
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+const NPM_REGISTRY_HOSTS: [&str; 2] =
+    ["https://registry.npmjs.org/", "https://registry.npmjs.com/"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum DeprecatedField {
     Text(String),
     Bool(bool),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl DeprecatedField {
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) if text.trim().is_empty() => None,
+            Self::Text(text) => Some(text.trim()),
+            Self::Bool(true) => Some("deprecated"),
+            Self::Bool(false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum BinField {
     Map(HashMap<String, String>),
     Str(String),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum EnginesField {
     Map(HashMap<String, String>),
@@ -24,7 +42,94 @@ pub enum EnginesField {
     Seq(Vec<serde_json::Value>),
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl EnginesField {
+    pub fn get(&self, engine: &str) -> Option<&str> {
+        match self {
+            Self::Map(map) => map.get(engine).map(String::as_str),
+            Self::Str(value) => engine_range(value, engine),
+            Self::Seq(values) => {
+                values.iter().filter_map(|v| v.as_str()).find_map(|v| engine_range(v, engine))
+            }
+            Self::Bool(_) => None,
+        }
+    }
+}
+
+fn engine_range<'a>(value: &'a str, engine: &str) -> Option<&'a str> {
+    let range = value.trim().strip_prefix(engine)?;
+    range.starts_with(|c: char| !c.is_alphanumeric()).then(|| range.trim())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum PlatformField {
+    Seq(Vec<String>),
+    Str(String),
+}
+
+impl PlatformField {
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            Self::Seq(values) => values.iter().map(String::as_str).collect(),
+            Self::Str(value) => vec![value.as_str()],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum BundledField {
+    Seq(Vec<String>),
+    Bool(bool),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PeerDependencyMeta {
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetadataKind {
+    Abbreviated,
+    Full,
+}
+
+impl MetadataKind {
+    pub fn accept(&self) -> &'static str {
+        match self {
+            Self::Abbreviated => {
+                "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8, */*"
+            }
+            Self::Full => "application/json",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AbbreviatedPackage {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub modified: Option<String>,
+    #[serde(default)]
+    pub versions: HashMap<String, PackageVersion>,
+    #[serde(rename = "dist-tags", default)]
+    pub dist_tags: HashMap<String, String>,
+}
+
+impl From<AbbreviatedPackage> for RegistryPackage {
+    fn from(package: AbbreviatedPackage) -> Self {
+        Self {
+            name: package.name,
+            versions: package.versions,
+            dist_tags: package.dist_tags,
+            time: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RegistryPackage {
     #[serde(default)]
     pub name: String,
@@ -32,9 +137,11 @@ pub struct RegistryPackage {
     pub versions: HashMap<String, PackageVersion>,
     #[serde(rename = "dist-tags", default)]
     pub dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    pub time: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct PackageVersion {
     pub name: String,
     pub version: String,
@@ -43,8 +150,11 @@ pub struct PackageVersion {
     pub dev_dependencies: Option<HashMap<String, String>>,
     #[serde(rename = "peerDependencies")]
     pub peer_dependencies: Option<HashMap<String, String>>,
+    #[serde(rename = "peerDependenciesMeta", default)]
+    pub peer_dependencies_meta: Option<HashMap<String, PeerDependencyMeta>>,
     #[serde(rename = "optionalDependencies")]
     pub optional_dependencies: Option<HashMap<String, String>>,
+    #[serde(default)]
     pub dist: DistInfo,
     #[serde(default)]
     pub engines: Option<EnginesField>,
@@ -52,11 +162,23 @@ pub struct PackageVersion {
     pub bin: Option<BinField>,
     #[serde(default)]
     pub deprecated: Option<DeprecatedField>,
+    #[serde(default)]
+    pub os: Option<PlatformField>,
+    #[serde(default)]
+    pub cpu: Option<PlatformField>,
+    #[serde(default)]
+    pub libc: Option<PlatformField>,
+    #[serde(rename = "bundleDependencies", default)]
+    pub bundle_dependencies: Option<BundledField>,
+    #[serde(rename = "bundledDependencies", default)]
+    pub bundled_dependencies: Option<BundledField>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DistInfo {
+    #[serde(default)]
     pub tarball: String,
+    #[serde(default)]
     pub shasum: String,
     pub integrity: Option<String>,
     #[serde(rename = "fileCount", default)]
@@ -65,5 +187,142 @@ pub struct DistInfo {
     pub unpacked_size: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VersionedPackage(pub PackageVersion);
+
+impl PackageVersion {
+    pub fn from_manifest(bytes: &[u8]) -> Option<Self> {
+        let mut manifest: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+        let object = manifest.as_object_mut()?;
+
+        object.entry("version").or_insert_with(|| "0.0.0".into());
+
+        serde_json::from_value(manifest).ok()
+    }
+
+    pub fn bundled(&self) -> Vec<String> {
+        let mut names: Vec<String> =
+            match self.bundle_dependencies.as_ref().or(self.bundled_dependencies.as_ref()) {
+                Some(BundledField::Seq(names)) => names.clone(),
+                Some(BundledField::Bool(true)) => [&self.dependencies, &self.optional_dependencies]
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|deps| deps.keys().cloned())
+                    .collect(),
+                Some(BundledField::Bool(false)) | None => Vec::new(),
+            };
+
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+pub fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+        return Some(datetime.and_utc());
+    }
+
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0).map(|d| d.and_utc())
+}
+
+pub fn registry_for(name: &str) -> String {
+    registry_in(&CONFIG, name)
+}
+
+fn registry_in(config: &Config, name: &str) -> String {
+    let scoped = name
+        .strip_prefix('@')
+        .and_then(|rest| rest.split_once('/'))
+        .and_then(|(scope, _)| config.get(&format!("@{scope}:registry")));
+
+    let registry = scoped.or_else(|| config.get("registry")).unwrap_or(DEFAULT_REGISTRY);
+    match registry.ends_with('/') {
+        true => registry.to_string(),
+        false => format!("{registry}/"),
+    }
+}
+
+pub fn encode_name(name: &str) -> String {
+    match name.starts_with('@') {
+        true => name.replacen('/', "%2f", 1),
+        false => name.to_string(),
+    }
+}
+
+pub fn package_url(name: &str) -> String {
+    format!("{}{}", registry_for(name), encode_name(name))
+}
+
+pub fn tarball_url(name: &str, tarball: &str) -> String {
+    rewrite_tarball(&registry_for(name), tarball)
+}
+
+fn rewrite_tarball(registry: &str, tarball: &str) -> String {
+    NPM_REGISTRY_HOSTS
+        .iter()
+        .find_map(|host| tarball.strip_prefix(host))
+        .filter(|_| !NPM_REGISTRY_HOSTS.contains(&registry))
+        .map(|path| format!("{registry}{path}"))
+        .unwrap_or_else(|| tarball.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG_TEXT: &str = "registry=https://npm.example.com/repository/npm\n\
+        @corp:registry=https://corp.example.com/npm/\n";
+
+    #[test]
+    fn scoped_names_use_their_scope_registry() {
+        let config = Config::from_ini(CONFIG_TEXT);
+
+        assert_eq!(registry_in(&config, "@corp/ui"), "https://corp.example.com/npm/");
+        assert_eq!(registry_in(&config, "@other/ui"), "https://npm.example.com/repository/npm/");
+        assert_eq!(registry_in(&config, "lodash"), "https://npm.example.com/repository/npm/");
+        assert_eq!(registry_in(&Config::from_ini(""), "@corp/ui"), DEFAULT_REGISTRY);
+    }
+
+    #[test]
+    fn encode_name_escapes_only_the_scope_separator() {
+        assert_eq!(encode_name("lodash"), "lodash");
+        assert_eq!(encode_name("@corp/ui"), "@corp%2fui");
+        assert_eq!(encode_name("@corp/ui/extra"), "@corp%2fui/extra");
+    }
+
+    #[test]
+    fn tarballs_follow_the_configured_registry() {
+        let registry = registry_in(&Config::from_ini(CONFIG_TEXT), "lodash");
+
+        assert_eq!(
+            rewrite_tarball(&registry, "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"),
+            "https://npm.example.com/repository/npm/lodash/-/lodash-4.17.21.tgz"
+        );
+        assert_eq!(
+            rewrite_tarball(&registry, "https://registry.npmjs.com/@corp/ui/-/ui-1.0.0.tgz"),
+            "https://npm.example.com/repository/npm/@corp/ui/-/ui-1.0.0.tgz"
+        );
+    }
+
+    #[test]
+    fn tarballs_are_kept_for_the_default_registry() {
+        let tarball = "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz";
+
+        assert_eq!(rewrite_tarball(DEFAULT_REGISTRY, tarball), tarball);
+        assert_eq!(rewrite_tarball("https://registry.npmjs.com/", tarball), tarball);
+    }
+
+    #[test]
+    fn foreign_tarballs_are_not_rewritten() {
+        let registry = registry_in(&Config::from_ini(CONFIG_TEXT), "lodash");
+        let tarball = "https://cdn.example.org/files/lodash-4.17.21.tgz";
+
+        assert_eq!(rewrite_tarball(&registry, tarball), tarball);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config::CONFIG;
+use crate::error::ClientError;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::{RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use utils::logger::*;
+
+pub static RETRY_POLICY: Lazy<RetryPolicy> = Lazy::new(RetryPolicy::from_config);
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub factor: u32,
+    pub min_timeout: Duration,
+    pub max_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            factor: 2,
+            min_timeout: Duration::from_millis(500),
+            max_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_config() -> Self {
+        let defaults = Self::default();
+        let number = |key: &str| {
+            let value = CONFIG.get(key)?;
+            let parsed = value.parse::<u64>().ok();
+            if parsed.is_none() {
+                warn(format!("ignoring invalid '{key}' setting: {value}"), false);
+            }
+            parsed
+        };
+
+        Self {
+            retries: number("fetch-retries").map_or(defaults.retries, |n| n as u32),
+            factor: number("fetch-retry-factor").map_or(defaults.factor, |n| n.max(1) as u32),
+            min_timeout: number("fetch-retry-mintimeout")
+                .map_or(defaults.min_timeout, Duration::from_millis),
+            max_timeout: number("fetch-retry-maxtimeout")
+                .map_or(defaults.max_timeout, Duration::from_millis),
+        }
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .min_timeout
+            .saturating_mul(self.factor.saturating_pow(attempt))
+            .min(self.max_timeout);
+
+        let jitter = RandomState::new().build_hasher().finish() % 1000;
+        delay / 2 + delay / 2 * jitter as u32 / 1000
+    }
+}
+
+pub async fn send(url: &str, build: impl Fn() -> RequestBuilder) -> Result<Response, ClientError> {
+    send_with(&RETRY_POLICY, url, build).await
+}
+
+async fn send_with(
+    policy: &RetryPolicy,
+    url: &str,
+    build: impl Fn() -> RequestBuilder,
+) -> Result<Response, ClientError> {
+    let mut attempt = 0;
+
+    loop {
+        let result = match build().send().await {
+            Ok(response) => classify(url, response),
+            Err(err) => Err(ClientError::from_reqwest(url, err)),
+        };
+
+        match result {
+            Err(err) if err.is_transient() && attempt < policy.retries => {
+                let delay = match err.retry_after() {
+                    Some(retry_after) => retry_after.min(policy.max_timeout),
+                    None => policy.backoff(attempt),
+                };
+                attempt += 1;
+                debug(
+                    format!("{err}, retrying in {delay:.1?} ({attempt}/{})", policy.retries),
+                    false,
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn classify(url: &str, response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+        return Ok(response);
+    }
+
+    let url = url.to_string();
+    let retry_after = retry_after(&response);
+
+    Err(match status {
+        StatusCode::NOT_FOUND => ClientError::NotFound { url },
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            ClientError::Unauthorized { url, status: status.as_u16() }
+        }
+        StatusCode::TOO_MANY_REQUESTS => ClientError::RateLimited { url, retry_after },
+        StatusCode::REQUEST_TIMEOUT => ClientError::Timeout { url },
+        status if status.is_server_error() => {
+            ClientError::Unavailable { url, status: status.as_u16(), retry_after }
+        }
+        status => ClientError::Status { url, status: status.as_u16() },
+    })
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    (date - Utc::now()).to_std().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::Client;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        retries: 2,
+        factor: 1,
+        min_timeout: Duration::from_millis(10),
+        max_timeout: Duration::from_secs(30),
+    };
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn serve(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/pkg", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0; 4096]);
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let response = &responses[hit.min(responses.len() - 1)];
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, hits)
+    }
+
+    async fn fetch(url: &str) -> Result<Response, ClientError> {
+        let client = Client::new();
+        send_with(&POLICY, url, || client.get(url)).await
+    }
+
+    #[tokio::test]
+    async fn not_found_is_not_retried() {
+        let (url, hits) = serve(vec![response("404 Not Found", "", "")]);
+        assert!(matches!(fetch(&url).await, Err(ClientError::NotFound { .. })));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_credentials_are_unauthorized() {
+        for status in ["401 Unauthorized", "403 Forbidden"] {
+            let (url, hits) = serve(vec![response(status, "", "")]);
+            let code = status[..3].parse::<u16>().unwrap();
+            assert!(matches!(
+                fetch(&url).await,
+                Err(ClientError::Unauthorized { status, .. }) if status == code
+            ));
+            assert_eq!(hits.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limits_honour_retry_after() {
+        let (url, hits) = serve(vec![
+            response("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+            response("200 OK", "", "{}"),
+        ]);
+
+        let start = Instant::now();
+        let response = fetch(&url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn unavailable_servers_are_retried_until_exhausted() {
+        let (url, hits) = serve(vec![response("503 Service Unavailable", "", "")]);
+        assert!(matches!(fetch(&url).await, Err(ClientError::Unavailable { status: 503, .. })));
+        assert_eq!(hits.load(Ordering::SeqCst), POLICY.retries as usize + 1);
+    }
+
+    #[tokio::test]
+    async fn malformed_bodies_fail_to_decode() {
+        let (url, _) = serve(vec![response("200 OK", "", "{not json")]);
+        let response = fetch(&url).await.unwrap();
+        let decoded = response.json::<serde_json::Value>().await;
+        let err = decoded.map_err(|err| ClientError::from_reqwest(&url, err)).unwrap_err();
+        assert!(matches!(err, ClientError::Decode { .. }));
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn backoff_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..10 {
+            let delay = policy.backoff(attempt);
+            assert!(delay <= policy.max_timeout);
+            assert!(delay >= policy.min_timeout / 2);
+        }
+    }
+}
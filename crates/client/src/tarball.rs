@@ -0,0 +1,52 @@
+use crate::auth::authorize;
+use crate::http;
+use crate::registry::PackageVersion;
+use crate::retry;
+
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use tar::Archive;
+
+use utils::logger::*;
+
+static CLIENT: Lazy<Client> =
+    Lazy::new(|| http::build(http::client_builder().timeout(Duration::from_secs(30))));
+
+pub async fn download(url: &str) -> Option<Vec<u8>> {
+    let response = match retry::send(url, || authorize(CLIENT.get(url), url)).await {
+        Ok(response) => response,
+        Err(err) => {
+            error(format!("http error: {err}"), false);
+            return None;
+        }
+    };
+
+    response.bytes().await.ok().map(|bytes| bytes.to_vec())
+}
+
+pub fn read_manifest(bytes: &[u8]) -> Option<PackageVersion> {
+    let mut archive = Archive::new(GzDecoder::new(bytes));
+
+    for entry in archive.entries().ok()? {
+        let mut entry = entry.ok()?;
+        let path = entry.path().ok()?.into_owned();
+
+        if path.components().count() == 2 && path.ends_with("package.json") {
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).ok()?;
+            return PackageVersion::from_manifest(&contents);
+        }
+    }
+
+    None
+}
+
+pub fn read_manifest_file(path: &Path) -> Option<PackageVersion> {
+    let bytes = std::fs::read(path).ok()?;
+    read_manifest(&bytes)
+}
@@ -1,4 +1,13 @@
-use crate::registry::{PackageVersion, RegistryPackage};
+use crate::auth::authorize;
+use crate::cache::{self, CachedPackument, NetworkMode, network_mode};
+use crate::error::ClientError;
+use crate::http;
+use crate::registry::{
+    AbbreviatedPackage, MetadataKind, PackageVersion, RegistryPackage, package_url, parse_timestamp,
+};
+use crate::retry;
+
+use chrono::{DateTime, Utc};
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -6,14 +15,54 @@ use std::time::{Duration, Instant};
 
 use once_cell::sync::Lazy;
 
-use reqwest::Client;
+use reqwest::header::{ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
 use tokio::sync::RwLock;
 
 use utils::logger::*;
 
+#[derive(Clone, Debug, Default)]
+pub struct PackageMetadata {
+    pub name: String,
+    pub versions: Vec<(String, PackageVersion)>,
+    pub dist_tags: HashMap<String, String>,
+    pub time: HashMap<String, DateTime<Utc>>,
+}
+
+impl PackageMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty()
+    }
+
+    pub fn get(&self, version: &str) -> Option<&PackageVersion> {
+        self.versions.iter().find(|(v, _)| v == version).map(|(_, data)| data)
+    }
+
+    pub fn published(&self, version: &str) -> Option<DateTime<Utc>> {
+        self.time.get(version).copied()
+    }
+
+    fn from_registry(name: &str, package: RegistryPackage) -> Self {
+        let time = package
+            .time
+            .iter()
+            .filter_map(|(version, value)| {
+                Some((version.clone(), parse_timestamp(value.as_str()?)?))
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            versions: package.versions.into_iter().collect(),
+            dist_tags: package.dist_tags,
+            time,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct CacheEntry {
-    versions: Vec<(String, PackageVersion)>,
+    metadata: PackageMetadata,
     timestamp: Instant,
 }
 
@@ -23,8 +72,11 @@ impl CacheEntry {
     }
 }
 
-static PACKAGE_CACHE: Lazy<Arc<RwLock<HashMap<String, CacheEntry>>>> =
-    Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+type PackageCache = Arc<RwLock<HashMap<(String, MetadataKind), CacheEntry>>>;
+
+static PACKAGE_CACHE: Lazy<PackageCache> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 
 #[derive(Clone, Debug)]
 pub struct RequestPackage {
@@ -33,62 +85,116 @@ pub struct RequestPackage {
 }
 
 impl RequestPackage {
-    pub async fn get_package_versions(&self) -> Vec<(String, PackageVersion)> {
-        const CACHE_TTL: Duration = Duration::from_secs(300);
+    pub async fn get_package_versions(&self) -> Result<PackageMetadata, ClientError> {
+        self.get_metadata(MetadataKind::Abbreviated).await
+    }
 
+    pub async fn get_metadata(&self, kind: MetadataKind) -> Result<PackageMetadata, ClientError> {
         {
             let cache = PACKAGE_CACHE.read().await;
-            if let Some(entry) = cache.get(&self.name) {
-                if !entry.is_expired(CACHE_TTL) {
-                    return entry.versions.clone();
+            let usable: &[MetadataKind] = match kind {
+                MetadataKind::Abbreviated => &[MetadataKind::Abbreviated, MetadataKind::Full],
+                MetadataKind::Full => &[MetadataKind::Full],
+            };
+            for kind in usable {
+                if let Some(entry) = cache.get(&(self.name.clone(), *kind))
+                    && !entry.is_expired(CACHE_TTL)
+                {
+                    return Ok(entry.metadata.clone());
                 }
             }
         }
 
         static CLIENT: Lazy<Client> = Lazy::new(|| {
-            Client::builder()
-                .tcp_keepalive(Duration::from_secs(60))
-                .pool_max_idle_per_host(30)
-                .pool_idle_timeout(Duration::from_secs(120))
-                .timeout(Duration::from_secs(10))
-                .connect_timeout(Duration::from_secs(5))
-                .build()
-                .unwrap_or_else(|_| Client::new())
+            http::build(
+                http::client_builder()
+                    .pool_max_idle_per_host(30)
+                    .pool_idle_timeout(Duration::from_secs(120))
+                    .timeout(Duration::from_secs(10)),
+            )
         });
 
-        let registry_url = format!("https://registry.npmjs.com/{}", self.name);
+        let cached = cache::read(&self.name, kind).await;
+
+        let package = match (network_mode(), cached) {
+            (NetworkMode::Offline, None) => {
+                return Err(ClientError::Offline { name: self.name.clone() });
+            }
+            (NetworkMode::Offline | NetworkMode::PreferOffline, Some(cached)) => {
+                debug(format!("using cached metadata for {}", self.name), false);
+                cached.package
+            }
+            (_, cached) => self.fetch(&CLIENT, kind, cached).await?,
+        };
 
-        let versions = match CLIENT.get(&registry_url).send().await {
-            Ok(response) => match response.json::<RegistryPackage>().await {
-                Ok(registry_package) => {
-                    let mut versions = Vec::with_capacity(registry_package.versions.len());
-                    for (version_str, pkg_version) in registry_package.versions {
-                        versions.push((version_str, pkg_version));
-                    }
+        let metadata = PackageMetadata::from_registry(&self.name, package);
+        self.remember(kind, &metadata).await;
+        Ok(metadata)
+    }
 
-                    versions
+    async fn fetch(
+        &self,
+        client: &Client,
+        kind: MetadataKind,
+        cached: Option<CachedPackument>,
+    ) -> Result<RegistryPackage, ClientError> {
+        let registry_url = package_url(&self.name);
+
+        let response = retry::send(&registry_url, || {
+            let mut request = authorize(client.get(&registry_url), &registry_url);
+            request = request.header(ACCEPT, kind.accept());
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
                 }
-                Err(e) => {
-                    error(format!("json parse error for {}: {e}", self.name), false);
-                    Vec::new()
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
                 }
-            },
-            Err(err) => {
-                error(format!("http error for {}: {err}", self.name), false);
-                Vec::new()
             }
+            request
+        })
+        .await;
+
+        let response = match (response, cached) {
+            (Ok(response), Some(cached)) if response.status() == StatusCode::NOT_MODIFIED => {
+                debug(format!("cached metadata for {} is still fresh", self.name), false);
+                return Ok(cached.package);
+            }
+            (Ok(response), _) => response,
+            (Err(err), Some(cached)) if err.is_transient() => {
+                warn(format!("{err}, using cached metadata for {}", self.name), false);
+                return Ok(cached.package);
+            }
+            (Err(err), _) => return Err(err),
         };
 
-        if !versions.is_empty() {
+        let header =
+            |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        let package = match kind {
+            MetadataKind::Abbreviated => {
+                response.json::<AbbreviatedPackage>().await.map(RegistryPackage::from)
+            }
+            MetadataKind::Full => response.json::<RegistryPackage>().await,
+        };
+        let package = package.map_err(|err| ClientError::from_reqwest(&registry_url, err))?;
+
+        let entry = CachedPackument { etag, last_modified, package };
+        cache::write(&self.name, kind, &entry).await;
+        Ok(entry.package)
+    }
+
+    async fn remember(&self, kind: MetadataKind, metadata: &PackageMetadata) {
+        if !metadata.is_empty() {
             let mut cache = PACKAGE_CACHE.write().await;
-            let entry = CacheEntry { versions: versions.clone(), timestamp: Instant::now() };
-            cache.insert(self.name.clone(), entry);
+            let entry = CacheEntry { metadata: metadata.clone(), timestamp: Instant::now() };
+            cache.insert((self.name.clone(), kind), entry);
 
             if cache.len() > 50 {
                 cache.retain(|_, entry| !entry.is_expired(CACHE_TTL));
             }
         }
-
-        versions
     }
 }
@@ -0,0 +1,245 @@
+use std::collections::{BTreeSet, HashMap};
+
+use utils::logger::*;
+
+use crate::lockfile::{Lockfile, package_key, package_name, package_version};
+use crate::semver::{Range, SemVer};
+use crate::specifier::Specifier;
+
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub name: String,
+    pub removed: Vec<String>,
+    pub kept: Vec<String>,
+}
+
+fn edge_accepts(specifier: &str, current: &str, candidate: &str) -> bool {
+    if current == candidate {
+        return true;
+    }
+
+    let range = match Specifier::parse(specifier) {
+        Some(Specifier::Range(range)) => range,
+        Some(Specifier::Alias { spec, .. }) => match *spec {
+            Specifier::Range(range) => range,
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    match (Range::parse(&range), SemVer::parse(candidate)) {
+        (Some(range), Some(version)) => range.satisfies(&version),
+        _ => false,
+    }
+}
+
+fn group_of(key: &str) -> String {
+    format!("{}{}", package_name(key), &key[package_key(key).len()..])
+}
+
+pub fn dedupe(lock: &mut Lockfile) -> Vec<Merge> {
+    let mut requests: HashMap<String, BTreeSet<(String, String)>> = HashMap::new();
+    for edge in lock.edges() {
+        if lock.packages.contains_key(&edge.package) {
+            let group = group_of(&edge.package);
+            let current = package_version(&edge.package).to_string();
+            requests
+                .entry(group)
+                .or_default()
+                .insert((edge.effective_specifier().to_string(), current));
+        }
+    }
+
+    let mut remap: HashMap<(String, String, String), String> = HashMap::new();
+    let mut merges = Vec::new();
+
+    let mut names: Vec<_> = requests.into_iter().collect();
+    names.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (group, requested) in names {
+        let mut candidates: Vec<String> =
+            requested.iter().map(|(_, current)| current.clone()).collect();
+        candidates.sort_by(|a, b| compare_versions(b, a));
+        candidates.dedup();
+
+        if candidates.len() < 2 {
+            continue;
+        }
+
+        let chosen = cover(&requested, &candidates);
+        if chosen.len() == candidates.len() {
+            continue;
+        }
+
+        for (specifier, current) in &requested {
+            let target = chosen
+                .iter()
+                .find(|candidate| edge_accepts(specifier, current, candidate))
+                .cloned()
+                .unwrap_or_else(|| current.clone());
+            remap.insert((group.clone(), specifier.clone(), current.clone()), target);
+        }
+
+        let removed = candidates.iter().filter(|c| !chosen.contains(c)).cloned().collect();
+        merges.push(Merge { name: group, removed, kept: chosen });
+    }
+
+    if merges.is_empty() {
+        return merges;
+    }
+
+    for edge in lock.edges_mut() {
+        let group = group_of(&edge.package);
+        let current = package_version(&edge.package).to_string();
+        let specifier = edge.effective_specifier().to_string();
+        if let Some(target) = remap.get(&(group, specifier, current)) {
+            let context = &edge.package[package_key(&edge.package).len()..];
+            edge.package = format!("{}@{target}{context}", package_name(&edge.package));
+        }
+    }
+
+    lock.prune();
+    merges
+}
+
+fn cover(requested: &BTreeSet<(String, String)>, candidates: &[String]) -> Vec<String> {
+    let mut uncovered: Vec<&(String, String)> = requested.iter().collect();
+    let mut chosen: Vec<String> = Vec::new();
+
+    while !uncovered.is_empty() {
+        let best =
+            candidates.iter().rev().filter(|c| !chosen.contains(c)).max_by_key(|candidate| {
+                uncovered.iter().filter(|(spec, cur)| edge_accepts(spec, cur, candidate)).count()
+            });
+
+        let Some(best) = best.cloned() else {
+            break;
+        };
+
+        uncovered.retain(|(spec, cur)| !edge_accepts(spec, cur, &best));
+        chosen.push(best);
+    }
+
+    chosen.sort_by(|a, b| compare_versions(b, a));
+    chosen
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (SemVer::parse(a), SemVer::parse(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+pub fn report(merges: &[Merge]) {
+    if merges.is_empty() {
+        return;
+    }
+
+    let removed: usize = merges.iter().map(|m| m.removed.len()).sum();
+    info(format!("Deduplicated {removed} package versions"), false);
+
+    for merge in merges {
+        let removed = merge.removed.join(", ");
+        sub_info(format!("{}: {removed} → {}", merge.name, merge.kept.join(", ")), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::lockfile::{LockedDependency, LockedPackage};
+
+    type Edge<'a> = (&'a str, &'a str, &'a str);
+
+    fn lockfile(root: &[Edge], packages: &[(&str, &[Edge])]) -> Lockfile {
+        let edges = |deps: &[Edge]| {
+            deps.iter()
+                .map(|(name, spec, key)| {
+                    (name.to_string(), LockedDependency::new(spec.to_string(), key.to_string()))
+                })
+                .collect()
+        };
+
+        let mut lockfile = Lockfile { dependencies: edges(root), ..Default::default() };
+        for (key, deps) in packages {
+            let package = LockedPackage {
+                name: package_name(key).to_string(),
+                version: package_version(key).to_string(),
+                dependencies: edges(deps),
+                ..Default::default()
+            };
+            lockfile.packages.insert(key.to_string(), package);
+        }
+        lockfile
+    }
+
+    #[test]
+    fn merges_overlapping_ranges_into_one_version() {
+        let mut lock = lockfile(
+            &[
+                ("lodash", "^4.17.0", "lodash@4.17.5"),
+                ("a", "^1.0.0", "a@1.0.0"),
+                ("b", "^1.0.0", "b@1.0.0"),
+            ],
+            &[
+                ("a@1.0.0", &[("lodash", "^4.17.0", "lodash@4.17.5")]),
+                ("b@1.0.0", &[("lodash", "^4.17.21", "lodash@4.17.21")]),
+                ("lodash@4.17.5", &[("legacy", "^1.0.0", "legacy@1.0.0")]),
+                ("lodash@4.17.21", &[]),
+                ("legacy@1.0.0", &[]),
+            ],
+        );
+
+        let merges = dedupe(&mut lock);
+
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].name, "lodash");
+        assert_eq!(merges[0].removed, ["4.17.5"]);
+        assert_eq!(merges[0].kept, ["4.17.21"]);
+
+        assert!(
+            lock.edges()
+                .filter(|edge| package_name(&edge.package) == "lodash")
+                .all(|edge| edge.package == "lodash@4.17.21")
+        );
+        assert_eq!(lock.edges().filter(|edge| edge.package == "lodash@4.17.21").count(), 3);
+        assert!(!lock.packages.contains_key("lodash@4.17.5"));
+        assert!(!lock.packages.contains_key("legacy@1.0.0"));
+    }
+
+    #[test]
+    fn keeps_versions_needed_by_disjoint_ranges() {
+        let mut lock = lockfile(
+            &[("a", "^1.0.0", "a@1.0.0"), ("lodash", "^4.0.0", "lodash@4.17.21")],
+            &[
+                ("a@1.0.0", &[("lodash", "^3.0.0", "lodash@3.10.1")]),
+                ("lodash@3.10.1", &[]),
+                ("lodash@4.17.21", &[]),
+            ],
+        );
+
+        assert!(dedupe(&mut lock).is_empty());
+        assert!(lock.packages.contains_key("lodash@3.10.1"));
+        assert!(lock.packages.contains_key("lodash@4.17.21"));
+    }
+
+    #[test]
+    fn does_not_merge_across_override_contexts() {
+        let mut lock = lockfile(
+            &[("x", "^1.0.0", "x@1.0.0(x>**>b:1)"), ("b", "^1.0.0", "b@1.1.0")],
+            &[
+                ("x@1.0.0(x>**>b:1)", &[("b", "^1.0.0", "b@1.0.0(x>**>b:1)")]),
+                ("b@1.1.0", &[]),
+                ("b@1.0.0(x>**>b:1)", &[]),
+            ],
+        );
+
+        assert!(dedupe(&mut lock).is_empty());
+        assert_eq!(
+            lock.packages["x@1.0.0(x>**>b:1)"].dependencies["b"].package,
+            "b@1.0.0(x>**>b:1)"
+        );
+    }
+}
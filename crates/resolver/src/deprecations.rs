@@ -0,0 +1,94 @@
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use utils::logger::*;
+
+use crate::graph::DependencyGraph;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Deprecation {
+    pub package: String,
+    pub name: String,
+    pub version: String,
+    pub message: String,
+    pub chain: Vec<String>,
+}
+
+pub fn collect(graph: &DependencyGraph) -> Vec<Deprecation> {
+    let parents = shortest_parents(graph);
+
+    graph
+        .nodes
+        .iter()
+        .filter_map(|(key, node)| {
+            let message = node.info.deprecated.as_ref()?.message()?;
+            Some(Deprecation {
+                package: key.clone(),
+                name: node.info.name.clone(),
+                version: node.info.version.clone(),
+                message: message.to_string(),
+                chain: chain_to(&parents, key),
+            })
+        })
+        .collect()
+}
+
+fn shortest_parents(graph: &DependencyGraph) -> HashMap<&str, Option<&str>> {
+    let mut parents: HashMap<&str, Option<&str>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for root in &graph.roots {
+        if parents.insert(root, None).is_none() {
+            queue.push_back(root.as_str());
+        }
+    }
+
+    while let Some(key) = queue.pop_front() {
+        let Some(node) = graph.nodes.get(key) else {
+            continue;
+        };
+        for dep in &node.dependencies {
+            if !parents.contains_key(dep.as_str()) {
+                parents.insert(dep, Some(key));
+                queue.push_back(dep);
+            }
+        }
+    }
+
+    parents
+}
+
+fn chain_to(parents: &HashMap<&str, Option<&str>>, key: &str) -> Vec<String> {
+    let mut chain = vec![key.to_string()];
+    let mut current = key;
+
+    while let Some(Some(parent)) = parents.get(current) {
+        chain.push(parent.to_string());
+        current = parent;
+    }
+
+    chain.reverse();
+    chain
+}
+
+pub fn report(deprecations: &[Deprecation], root: &str) {
+    if deprecations.is_empty() {
+        return;
+    }
+
+    let mut grouped: BTreeMap<&str, Vec<&Deprecation>> = BTreeMap::new();
+    for deprecation in deprecations {
+        grouped.entry(&deprecation.name).or_default().push(deprecation);
+    }
+
+    warn(format!("Deprecated packages ({})", deprecations.len()), false);
+
+    for (name, deprecations) in grouped {
+        sub_warn(name, false);
+        for deprecation in deprecations {
+            sub_sub_log(format!("{}: {}", deprecation.version, deprecation.message), false, false);
+            sub_sub_log(format!("  via {root} › {}", deprecation.chain.join(" › ")), false, false);
+        }
+    }
+}
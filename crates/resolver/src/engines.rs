@@ -0,0 +1,166 @@
+use client::config::CONFIG;
+use client::registry::PackageVersion;
+use once_cell::sync::Lazy;
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use utils::logger::*;
+
+use crate::semver::{Range, SemVer};
+
+static NODE_VERSION: Lazy<Option<SemVer>> = Lazy::new(|| detect_version("node"));
+static NPM_VERSION: Lazy<Option<SemVer>> = Lazy::new(|| detect_version("npm"));
+
+#[derive(Debug, Clone)]
+pub struct EngineIssue {
+    pub package: String,
+    pub engine: &'static str,
+    pub range: String,
+}
+
+pub fn node_version() -> Option<SemVer> {
+    NODE_VERSION.clone()
+}
+
+pub fn npm_version() -> Option<SemVer> {
+    NPM_VERSION.clone()
+}
+
+pub fn requires(info: &PackageVersion, engine: &str) -> bool {
+    info.engines.as_ref().and_then(|engines| engines.get(engine)).is_some()
+}
+
+fn detect_version(engine: &str) -> Option<SemVer> {
+    let key = format!("{engine}-version");
+    if let Some(value) = CONFIG.get(&key) {
+        let version = SemVer::parse(value);
+        if version.is_none() {
+            warn(format!("ignoring invalid '{key}' setting: {value}"), false);
+        }
+        return version;
+    }
+
+    let output = Command::new(engine).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    SemVer::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn satisfies(info: &PackageVersion, engine: &str, version: &SemVer) -> bool {
+    let Some(range) = info.engines.as_ref().and_then(|engines| engines.get(engine)) else {
+        return true;
+    };
+
+    Range::parse_with(range, true).is_none_or(|range| range.satisfies(version))
+}
+
+pub fn supports(info: &PackageVersion, node: &SemVer) -> bool {
+    satisfies(info, "node", node)
+}
+
+pub fn check<'a>(
+    packages: impl IntoIterator<Item = &'a PackageVersion>,
+    node: Option<&SemVer>,
+    npm: Option<&SemVer>,
+) -> Vec<EngineIssue> {
+    let engines = [("node", node), ("npm", npm)];
+
+    let mut issues: Vec<EngineIssue> = packages
+        .into_iter()
+        .flat_map(|info| {
+            engines.iter().filter_map(move |(engine, version)| {
+                if satisfies(info, engine, (*version)?) {
+                    return None;
+                }
+                let range = info.engines.as_ref()?.get(engine)?;
+                Some(EngineIssue {
+                    package: format!("{}@{}", info.name, info.version),
+                    engine,
+                    range: range.to_string(),
+                })
+            })
+        })
+        .collect();
+
+    issues.sort_by(|a, b| (&a.package, a.engine).cmp(&(&b.package, b.engine)));
+    issues.dedup_by(|a, b| a.package == b.package && a.engine == b.engine);
+    issues
+}
+
+pub fn report(issues: &[EngineIssue], versions: &[(&str, &SemVer)], strict: bool) {
+    if issues.is_empty() {
+        return;
+    }
+
+    let detected: Vec<String> =
+        versions.iter().map(|(engine, version)| format!("{engine} {version}")).collect();
+    let header = format!("Unsupported engine ({}, {} packages)", detected.join(", "), issues.len());
+    match strict {
+        true => error(header, false),
+        false => warn(header, false),
+    }
+
+    let mut grouped: BTreeMap<(&str, &str), Vec<&EngineIssue>> = BTreeMap::new();
+    for issue in issues {
+        grouped.entry((issue.engine, &issue.range)).or_default().push(issue);
+    }
+
+    for ((engine, range), issues) in grouped {
+        sub_warn(format!("{engine} {range}"), false);
+        for issue in issues {
+            sub_sub_log(&issue.package, false, strict);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, engines: serde_json::Value) -> PackageVersion {
+        let manifest = serde_json::json!({"name": name, "version": "1.0.0", "engines": engines});
+        serde_json::from_value(manifest).expect("valid manifest")
+    }
+
+    fn version(v: &str) -> SemVer {
+        SemVer::parse(v).expect("valid version")
+    }
+
+    fn issues(packages: &[PackageVersion], node: Option<&str>, npm: Option<&str>) -> Vec<String> {
+        let (node, npm) = (node.map(version), npm.map(version));
+        check(packages, node.as_ref(), npm.as_ref())
+            .into_iter()
+            .map(|issue| format!("{} {} {}", issue.package, issue.engine, issue.range))
+            .collect()
+    }
+
+    #[test]
+    fn checks_node_and_npm_ranges() {
+        let packages = [
+            package("both", serde_json::json!({"node": ">=20", "npm": ">=10"})),
+            package("node", serde_json::json!({"node": "^18.0.0"})),
+            package("npm", serde_json::json!({"npm": "<9"})),
+            package("none", serde_json::json!({})),
+        ];
+
+        assert_eq!(
+            issues(&packages, Some("18.19.0"), Some("9.8.1")),
+            ["both@1.0.0 node >=20", "both@1.0.0 npm >=10", "npm@1.0.0 npm <9"]
+        );
+        assert_eq!(
+            issues(&packages, Some("20.11.0"), Some("10.2.4")),
+            ["node@1.0.0 node ^18.0.0", "npm@1.0.0 npm <9"]
+        );
+    }
+
+    #[test]
+    fn skips_engines_without_a_detected_version() {
+        let packages = [package("both", serde_json::json!({"node": ">=20", "npm": ">=10"}))];
+
+        assert_eq!(issues(&packages, Some("18.19.0"), None), ["both@1.0.0 node >=20"]);
+        assert_eq!(issues(&packages, None, Some("9.8.1")), ["both@1.0.0 npm >=10"]);
+        assert!(issues(&packages, None, None).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::graph::{DAGNode, DependencyGraph};
+
+const GRAPH_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "dot" | "graphviz" => Ok(Self::Dot),
+            "mermaid" | "mmd" => Ok(Self::Mermaid),
+            other => Err(format!("unknown graph format '{other}' (expected json, dot or mermaid)")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonGraph<'a> {
+    version: u32,
+    roots: &'a [String],
+    nodes: Vec<JsonNode<'a>>,
+    cycles: Vec<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    id: &'a str,
+    name: &'a str,
+    version: &'a str,
+    unpacked_size: Option<u64>,
+    file_count: Option<u64>,
+    dependencies: &'a [String],
+}
+
+pub fn render(graph: &DependencyGraph, format: Format) -> String {
+    match format {
+        Format::Json => to_json(graph),
+        Format::Dot => to_dot(graph),
+        Format::Mermaid => to_mermaid(graph),
+    }
+}
+
+pub fn to_json(graph: &DependencyGraph) -> String {
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|(key, node)| JsonNode {
+            id: key,
+            name: &node.info.name,
+            version: &node.info.version,
+            unpacked_size: node.info.dist.unpacked_size,
+            file_count: node.info.dist.file_count,
+            dependencies: &node.dependencies,
+        })
+        .collect();
+
+    let json = JsonGraph {
+        version: GRAPH_SCHEMA_VERSION,
+        roots: &graph.roots,
+        nodes,
+        cycles: graph.cycles(),
+    };
+
+    serde_json::to_string_pretty(&json).unwrap_or_default()
+}
+
+pub fn to_dot(graph: &DependencyGraph) -> String {
+    let mut out = String::from("digraph dependencies {\n");
+    out.push_str("  rankdir=LR;\n");
+    out.push_str("  node [shape=box, fontname=\"Helvetica\"];\n");
+
+    for (key, node) in &graph.nodes {
+        let mut attrs = vec![format!("label=\"{}\"", label(&escape(key), node, "\\n"))];
+        if let Some(size) = node.info.dist.unpacked_size {
+            attrs.push(format!("tooltip=\"{size} bytes\""));
+        }
+        if graph.roots.contains(key) {
+            attrs.push("style=bold".to_string());
+        }
+        let _ = writeln!(out, "  \"{}\" [{}];", escape(key), attrs.join(", "));
+    }
+
+    for (key, node) in &graph.nodes {
+        for dep in &node.dependencies {
+            let _ = writeln!(out, "  \"{}\" -> \"{}\";", escape(key), escape(dep));
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+pub fn to_mermaid(graph: &DependencyGraph) -> String {
+    let ids: HashMap<&str, String> =
+        graph.nodes.keys().enumerate().map(|(i, key)| (key.as_str(), format!("n{i}"))).collect();
+
+    let mut out = String::from("graph LR\n");
+
+    for (key, node) in &graph.nodes {
+        let text = label(&key.replace('"', "#quot;"), node, "<br/>");
+        let _ = writeln!(out, "  {}[\"{text}\"]", ids[key.as_str()]);
+    }
+
+    for (key, node) in &graph.nodes {
+        for dep in &node.dependencies {
+            if let Some(to) = ids.get(dep.as_str()) {
+                let _ = writeln!(out, "  {} --> {to}", ids[key.as_str()]);
+            }
+        }
+    }
+
+    out
+}
+
+fn label(key: &str, node: &DAGNode, separator: &str) -> String {
+    match node.info.dist.unpacked_size {
+        Some(size) => format!("{key}{separator}{}", format_size(size)),
+        None => key.to_string(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "kB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
@@ -1,270 +1,323 @@
 use once_cell::sync::Lazy;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
-use futures::stream::FuturesUnordered;
-use futures_util::StreamExt;
+use futures::future::join_all;
 use tokio::sync::{RwLock, Semaphore};
 
-use crate::semver;
-use client::{registry::PackageVersion, versions::RequestPackage};
-
-type PackageVersionsMap = HashMap<String, Vec<(String, PackageVersion)>>;
-type SharedPackageCache = Arc<RwLock<PackageVersionsMap>>;
+use crate::dedupe::{Merge, dedupe};
+use crate::lockfile::{DependencyKind, LockedDependency, Lockfile};
+use crate::manifest::Manifest;
+use crate::overrides::Overrides;
+use crate::peers::PeerIssue;
+use crate::platform::Platform;
+use crate::semver::SelectOptions;
+use crate::solver::{Conflict, Dependency, Solver};
+use client::{
+    error::ClientError,
+    registry::{MetadataKind, PackageVersion},
+    versions::{PackageMetadata, RequestPackage},
+};
+
+type PackageMetadataMap = HashMap<String, Result<PackageMetadata, ClientError>>;
+type SharedPackageCache = Arc<RwLock<PackageMetadataMap>>;
 
 static GLOBAL_PACKAGE_CACHE: Lazy<SharedPackageCache> =
     Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 
-static GLOBAL_RESOLUTION_CACHE: Lazy<Arc<RwLock<HashMap<String, String>>>> =
-    Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
-
 #[derive(Debug, Clone)]
 pub struct DAGNode {
     pub package: String,
     pub dependencies: Vec<String>,
+    pub edges: BTreeMap<String, LockedDependency>,
     pub info: PackageVersion,
 }
 
 #[derive(Debug, Default)]
 pub struct DependencyGraph {
-    pub nodes: HashMap<String, DAGNode>,
+    pub roots: Vec<String>,
+    pub root_edges: BTreeMap<String, LockedDependency>,
+    pub nodes: BTreeMap<String, DAGNode>,
 }
 
 impl DependencyGraph {
     pub fn new() -> Self {
-        Self { nodes: HashMap::new() }
+        Self::default()
     }
 
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self { nodes: HashMap::with_capacity(capacity) }
+    pub fn from_lockfile(lockfile: &Lockfile, packages: &HashMap<String, PackageVersion>) -> Self {
+        let mut graph = Self::new();
+        graph.roots = lockfile.dependencies.values().map(|d| d.package.clone()).collect();
+        graph.roots.sort();
+        graph.roots.dedup();
+        graph.root_edges = lockfile.dependencies.clone();
+
+        for (key, locked) in &lockfile.packages {
+            let mut dependencies: Vec<String> =
+                locked.dependencies.values().map(|d| d.package.clone()).collect();
+            dependencies.sort();
+            dependencies.dedup();
+
+            let info = packages.get(key).cloned().unwrap_or_else(|| locked.to_package_version());
+            graph.add_node(DAGNode {
+                package: key.clone(),
+                dependencies,
+                edges: locked.dependencies.clone(),
+                info,
+            });
+        }
+
+        graph
     }
 
     pub fn add_node(&mut self, node: DAGNode) {
         self.nodes.insert(node.package.clone(), node);
     }
 
-    pub fn topological_sort(&self) -> Vec<String> {
-        if self.nodes.is_empty() {
-            return Vec::new();
-        }
-
-        let mut result = Vec::with_capacity(self.nodes.len());
-        let mut processed = HashSet::with_capacity(self.nodes.len());
-
-        fn dfs_visit(
-            pkg: &str,
-            graph: &HashMap<String, DAGNode>,
-            result: &mut Vec<String>,
-            processed: &mut HashSet<String>,
-        ) {
-            if processed.contains(pkg) {
-                return;
-            }
-
-            processed.insert(pkg.to_string());
-
-            if let Some(node) = graph.get(pkg) {
-                for dep in &node.dependencies {
-                    dfs_visit(dep, graph, result, processed);
+    pub fn subgraph(&self, roots: Vec<String>, depth: Option<usize>) -> Self {
+        let mut graph = Self::new();
+        let mut frontier: Vec<String> =
+            roots.iter().filter(|key| self.nodes.contains_key(*key)).cloned().collect();
+        let mut level = 0;
+
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for key in frontier {
+                if graph.nodes.contains_key(&key) {
+                    continue;
+                }
+                let node = self.nodes[&key].clone();
+                if depth.is_none_or(|depth| level < depth) {
+                    next.extend(node.dependencies.iter().cloned());
                 }
+                graph.add_node(node);
             }
-
-            result.push(pkg.to_string());
+            frontier = next;
+            level += 1;
         }
 
-        for pkg in self.nodes.keys() {
-            dfs_visit(pkg, &self.nodes, &mut result, &mut processed);
+        let included: HashSet<String> = graph.nodes.keys().cloned().collect();
+        for node in graph.nodes.values_mut() {
+            node.dependencies.retain(|dep| included.contains(dep));
+            node.edges.retain(|_, edge| included.contains(&edge.package));
         }
 
-        result
+        graph.roots = roots.into_iter().filter(|key| included.contains(key)).collect();
+        graph.root_edges = self
+            .root_edges
+            .iter()
+            .filter(|(_, edge)| graph.roots.contains(&edge.package))
+            .map(|(name, edge)| (name.clone(), edge.clone()))
+            .collect();
+        graph
     }
-}
 
-pub struct DAGBuilder {
-    resolution_cache: Arc<RwLock<HashMap<String, String>>>,
-    semaphore: Arc<Semaphore>,
-}
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let keys: Vec<&String> = self.nodes.keys().collect();
+        let index_of: HashMap<&str, usize> =
+            keys.iter().enumerate().map(|(i, key)| (key.as_str(), i)).collect();
+        let edges: Vec<Vec<usize>> = keys
+            .iter()
+            .map(|key| {
+                self.nodes[*key]
+                    .dependencies
+                    .iter()
+                    .filter_map(|dep| index_of.get(dep.as_str()).copied())
+                    .collect()
+            })
+            .collect();
+
+        let mut index = vec![UNVISITED; keys.len()];
+        let mut low = vec![0; keys.len()];
+        let mut on_stack = vec![false; keys.len()];
+        let mut stack = Vec::new();
+        let mut next = 0;
+        let mut components = Vec::new();
+
+        for start in 0..keys.len() {
+            if index[start] != UNVISITED {
+                continue;
+            }
 
-impl DAGBuilder {
-    pub fn new() -> Self {
-        Self {
-            resolution_cache: GLOBAL_RESOLUTION_CACHE.clone(),
-            semaphore: Arc::new(Semaphore::new(100)),
-        }
-    }
+            let mut work = vec![(start, 0)];
+            while let Some((v, pos)) = work.pop() {
+                if pos == 0 {
+                    index[v] = next;
+                    low[v] = next;
+                    next += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                }
 
-    pub async fn build_missing_only(&self, packages: Vec<RequestPackage>) -> Vec<PackageVersion> {
-        let mut all_resolved = Vec::new();
-        let mut processed = HashSet::new();
-        let mut to_process = packages;
-
-        while !to_process.is_empty() {
-            let current_batch = std::mem::take(&mut to_process);
-
-            let mut futs = FuturesUnordered::new();
-            for pkg in current_batch {
-                let semaphore = self.semaphore.clone();
-                let b = self;
-                futs.push(async move {
-                    let _permit = semaphore.acquire().await.ok()?;
-                    b.resolve_single(pkg).await
-                });
-            }
+                if let Some(&w) = edges[v].get(pos) {
+                    work.push((v, pos + 1));
+                    if index[w] == UNVISITED {
+                        work.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
 
-            while let Some(res_opt) = futs.next().await {
-                if let Some(result) = res_opt {
-                    let key = format!("{}@{}", result.info.name, result.info.version);
-                    if processed.insert(key.clone()) {
-                        for dep in &result.dependencies {
-                            if let Some(pos) = dep.rfind('@') {
-                                let dep_name = dep[..pos].to_string();
-                                let dep_version = dep[pos + 1..].to_string();
-                                let dep_key = format!("{dep_name}@{dep_version}");
-                                if !processed.contains(&dep_key) {
-                                    to_process.push(RequestPackage {
-                                        name: dep_name,
-                                        version: Some(dep_version),
-                                    });
-                                }
-                            }
+                if low[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(keys[w].clone());
+                        if w == v {
+                            break;
                         }
-                        all_resolved.push(result.info);
                     }
+                    component.sort();
+                    components.push(component);
+                }
+
+                if let Some(&(parent, _)) = work.last() {
+                    low[parent] = low[parent].min(low[v]);
                 }
             }
         }
 
-        all_resolved
+        components
     }
 
-    pub async fn build(&self, package: RequestPackage) -> Arc<RwLock<DependencyGraph>> {
-        let packages = self.build_missing_only(vec![package]).await;
-        let mut graph = DependencyGraph::with_capacity(packages.len());
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|component| match component.as_slice() {
+                [key] => self.nodes[key].dependencies.contains(key),
+                _ => true,
+            })
+            .collect()
+    }
 
-        for package_version in packages {
-            let package_key = format!("{}@{}", package_version.name, package_version.version);
-            let mut dep_keys = Vec::new();
-            if let Some(deps) = &package_version.dependencies {
-                for (dep_name, dep_version) in deps {
-                    dep_keys.push(format!("{dep_name}@{dep_version}"));
-                }
-            }
-            if let Some(opt_deps) = &package_version.optional_dependencies {
-                for (dep_name, dep_version) in opt_deps {
-                    dep_keys.push(format!("{dep_name}@{dep_version}"));
-                }
-            }
-            if let Some(peer_deps) = &package_version.peer_dependencies {
-                for (dep_name, dep_version) in peer_deps {
-                    dep_keys.push(format!("{dep_name}@{dep_version}"));
-                }
-            }
-            let node =
-                DAGNode { package: package_key, dependencies: dep_keys, info: package_version };
-            graph.add_node(node);
-        }
-        Arc::new(RwLock::new(graph))
+    pub fn topological_sort(&self) -> Vec<String> {
+        self.strongly_connected_components().into_iter().flatten().collect()
     }
+}
 
-    async fn resolve_single(&self, package: RequestPackage) -> Option<DAGNode> {
-        let name = &package.name;
-        let version_req = package.version.as_deref().unwrap_or("latest");
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub lockfile: Lockfile,
+    pub packages: HashMap<String, PackageVersion>,
+    pub merges: Vec<Merge>,
+    pub peer_issues: Vec<PeerIssue>,
+}
 
-        let cache_key = format!("{name}@{version_req}");
-        {
-            let cache = self.resolution_cache.read().await;
-            if let Some(final_key) = cache.get(&cache_key).cloned() {
-                drop(cache);
-                if let Some(pos) = final_key.rfind('@') {
-                    let pkg_name = &final_key[..pos];
-                    let pkg_version = &final_key[pos + 1..];
-                    let versions = self.get_cached_versions(pkg_name).await;
-                    if let Some((_, data)) = versions.into_iter().find(|(v, _)| v == pkg_version) {
-                        let mut dep_keys: Vec<String> = Vec::new();
-                        if let Some(deps) = &data.dependencies {
-                            for (dep_name, dep_version) in deps {
-                                dep_keys.push(format!("{dep_name}@{dep_version}"));
-                            }
-                        }
-                        if let Some(opt_deps) = &data.optional_dependencies {
-                            for (dep_name, dep_version) in opt_deps {
-                                dep_keys.push(format!("{dep_name}@{dep_version}"));
-                            }
-                        }
-                        if let Some(peer_deps) = &data.peer_dependencies {
-                            for (dep_name, dep_version) in peer_deps {
-                                dep_keys.push(format!("{dep_name}@{dep_version}"));
-                            }
-                        }
-                        return Some(DAGNode {
-                            package: final_key.clone(),
-                            dependencies: dep_keys,
-                            info: data,
-                        });
-                    }
-                }
-            }
-        }
+pub struct DAGBuilder {
+    pub(crate) semaphore: Arc<Semaphore>,
+    pub(crate) options: SelectOptions,
+    pub(crate) platform: Platform,
+}
 
-        let versions = self.get_cached_versions(name).await;
-        if versions.is_empty() {
-            return None;
-        }
+impl DAGBuilder {
+    pub fn new() -> Self {
+        Self::with_options(SelectOptions::from_config())
+    }
 
-        let available: Vec<&str> = versions.iter().map(|(v, _)| v.as_str()).collect();
-        let selected_version = semver::select_version(version_req, available)?;
+    pub fn with_options(options: SelectOptions) -> Self {
+        Self { semaphore: Arc::new(Semaphore::new(100)), options, platform: Platform::current() }
+    }
 
-        let pkg_version =
-            versions.into_iter().find(|(v, _)| v == &selected_version).map(|(_, data)| data)?;
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
 
-        let final_key = format!("{name}@{selected_version}");
+    pub async fn resolve_manifest(&self, manifest: &Manifest) -> Result<Resolution, Conflict> {
+        let deps = manifest
+            .requests()
+            .into_iter()
+            .map(|request| {
+                let kind = manifest.kind(&request.name);
+                Dependency::from_request(request, kind)
+            })
+            .collect();
+
+        self.solve(manifest.root_name(), deps, Overrides::from_manifest(manifest)).await
+    }
 
-        {
-            let mut cache = self.resolution_cache.write().await;
-            cache.insert(cache_key, final_key.clone());
-        }
+    pub async fn resolve(
+        &self,
+        root: &str,
+        packages: Vec<RequestPackage>,
+    ) -> Result<Resolution, Conflict> {
+        let deps = packages
+            .into_iter()
+            .map(|pkg| Dependency::from_request(pkg, DependencyKind::Prod))
+            .collect();
+        self.solve(root, deps, Overrides::default()).await
+    }
 
-        let mut dep_keys: Vec<String> = Vec::new();
+    async fn solve(
+        &self,
+        root: &str,
+        deps: Vec<Dependency>,
+        overrides: Overrides,
+    ) -> Result<Resolution, Conflict> {
+        let mut solver = Solver::new(self, overrides);
+        let mut lockfile = solver.solve(root, deps).await?;
 
-        if let Some(deps) = &pkg_version.dependencies {
-            for (dep_name, dep_version) in deps {
-                dep_keys.push(format!("{dep_name}@{dep_version}"));
-            }
-        }
+        let merges = dedupe(&mut lockfile);
+        let peer_issues = std::mem::take(&mut solver.peer_issues);
+        let packages = solver.into_packages(&lockfile);
 
-        if let Some(opt_deps) = &pkg_version.optional_dependencies {
-            for (dep_name, dep_version) in opt_deps {
-                dep_keys.push(format!("{dep_name}@{dep_version}"));
-            }
-        }
+        Ok(Resolution { lockfile, packages, merges, peer_issues })
+    }
 
-        if let Some(peer_deps) = &pkg_version.peer_dependencies {
-            for (dep_name, dep_version) in peer_deps {
-                dep_keys.push(format!("{dep_name}@{dep_version}"));
+    pub async fn hydrate(&self, graph: &mut DependencyGraph) {
+        let names: HashSet<String> = graph
+            .nodes
+            .values()
+            .filter(|node| node.info.dist.tarball.starts_with("http"))
+            .map(|node| node.info.name.clone())
+            .collect();
+
+        let metadata: HashMap<String, PackageMetadata> =
+            join_all(names.into_iter().map(|name| async move {
+                let _permit = self.semaphore.acquire().await.ok();
+                let metadata = self.get_cached_versions(&name).await;
+                (name, metadata)
+            }))
+            .await
+            .into_iter()
+            .filter_map(|(name, metadata)| Some((name, metadata.ok()?)))
+            .collect();
+
+        for node in graph.nodes.values_mut() {
+            if let Some(info) =
+                metadata.get(&node.info.name).and_then(|m| m.get(&node.info.version))
+            {
+                node.info = info.clone();
             }
         }
-
-        Some(DAGNode { package: final_key, dependencies: dep_keys, info: pkg_version })
     }
 
-    async fn get_cached_versions(&self, name: &str) -> Vec<(String, PackageVersion)> {
+    pub(crate) async fn get_cached_versions(
+        &self,
+        name: &str,
+    ) -> Result<PackageMetadata, ClientError> {
         {
             let cache = GLOBAL_PACKAGE_CACHE.read().await;
-            if let Some(versions) = cache.get(name) {
-                return versions.clone();
+            if let Some(metadata) = cache.get(name) {
+                return metadata.clone();
             }
         }
 
         let req_pkg = RequestPackage { name: name.to_string(), version: None };
-        let versions = req_pkg.get_package_versions().await;
+        let kind = match self.options.needs_publish_times() {
+            true => MetadataKind::Full,
+            false => MetadataKind::Abbreviated,
+        };
+        let metadata = req_pkg.get_metadata(kind).await;
 
-        if !versions.is_empty() {
-            let mut cache = GLOBAL_PACKAGE_CACHE.write().await;
-            cache.insert(name.to_string(), versions.clone());
-        }
+        let mut cache = GLOBAL_PACKAGE_CACHE.write().await;
+        cache.insert(name.to_string(), metadata.clone());
 
-        versions
+        metadata
     }
 }
 
@@ -273,3 +326,90 @@ impl Default for DAGBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+pub(crate) async fn stub_registry(packages: serde_json::Value) {
+    let mut cache = GLOBAL_PACKAGE_CACHE.write().await;
+    for (name, versions) in packages.as_object().into_iter().flatten() {
+        let versions = versions
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(version, manifest)| {
+                let mut manifest = manifest.clone();
+                manifest["name"] = name.as_str().into();
+                manifest["version"] = version.as_str().into();
+                let info = serde_json::from_value(manifest).expect("valid manifest");
+                (version.clone(), info)
+            })
+            .collect();
+        let metadata = PackageMetadata { name: name.clone(), versions, ..Default::default() };
+        cache.insert(name.clone(), Ok(metadata));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for (key, dependencies) in edges {
+            graph.add_node(DAGNode {
+                package: key.to_string(),
+                dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+                edges: BTreeMap::new(),
+                info: PackageVersion::default(),
+            });
+        }
+        graph
+    }
+
+    fn position(order: &[String], key: &str) -> usize {
+        order.iter().position(|k| k == key).expect("key in order")
+    }
+
+    #[test]
+    fn two_node_cycles_form_one_component() {
+        let graph = graph(&[("a", &["b"]), ("b", &["a", "c"]), ("c", &[])]);
+
+        let components = graph.strongly_connected_components();
+        assert_eq!(components.len(), 2);
+        assert!(components.contains(&vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(graph.cycles(), [["a", "b"]]);
+    }
+
+    #[test]
+    fn self_loops_are_cycles() {
+        let graph = graph(&[("a", &["a", "b"]), ("b", &[])]);
+        assert_eq!(graph.cycles(), [["a"]]);
+    }
+
+    #[test]
+    fn acyclic_graphs_have_no_cycles() {
+        let graph = graph(&[("a", &["b", "c"]), ("b", &["c"]), ("c", &[])]);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(graph.strongly_connected_components().len(), 3);
+    }
+
+    #[test]
+    fn topological_sort_puts_dependencies_first() {
+        let edges: &[(&str, &[&str])] =
+            &[("app", &["b", "a"]), ("a", &["c", "d"]), ("b", &["c"]), ("c", &["d"]), ("d", &[])];
+        let order = graph(edges).topological_sort();
+
+        assert_eq!(order.len(), edges.len());
+        for (key, dependencies) in edges {
+            for dep in *dependencies {
+                assert!(position(&order, dep) < position(&order, key), "{dep} before {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn topological_sort_keeps_cycles_after_their_dependencies() {
+        let order = graph(&[("x", &["y"]), ("y", &["x", "z"]), ("z", &[])]).topological_sort();
+        assert!(position(&order, "z") < position(&order, "x"));
+        assert!(position(&order, "z") < position(&order, "y"));
+    }
+}
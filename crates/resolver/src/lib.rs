@@ -1,2 +1,15 @@
+pub mod dedupe;
+pub mod deprecations;
+pub mod engines;
+pub mod export;
 pub mod graph;
+pub mod lockfile;
+pub mod manifest;
+pub mod overrides;
+pub mod peers;
+pub mod platform;
 pub mod semver;
+pub mod solver;
+pub mod sources;
+pub mod specifier;
+pub mod why;
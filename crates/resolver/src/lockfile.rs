@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::{read, write};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use client::integrity::Integrity;
+use client::registry::{BundledField, DeprecatedField, DistInfo, EnginesField, PackageVersion};
+
+use crate::manifest::Manifest;
+use crate::overrides::AppliedOverride;
+use crate::specifier::split_name_spec;
+
+pub const LOCKFILE_NAME: &str = "package.lock";
+const LOCKFILE_VERSION: u32 = 1;
+const ENGINES: [&str; 2] = ["node", "npm"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    #[default]
+    Prod,
+    Dev,
+    Optional,
+    Peer,
+}
+
+impl DependencyKind {
+    fn is_prod(&self) -> bool {
+        *self == Self::Prod
+    }
+}
+
+impl FromStr for DependencyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "prod" => Ok(Self::Prod),
+            "dev" => Ok(Self::Dev),
+            "optional" => Ok(Self::Optional),
+            "peer" => Ok(Self::Peer),
+            other => Err(format!("unknown dependency kind '{other}'")),
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Prod => "prod",
+            Self::Dev => "dev",
+            Self::Optional => "optional",
+            Self::Peer => "peer",
+        };
+        f.write_str(kind)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedDependency {
+    pub specifier: String,
+    pub package: String,
+    #[serde(default, skip_serializing_if = "DependencyKind::is_prod")]
+    pub kind: DependencyKind,
+    #[serde(rename = "override", default, skip_serializing_if = "Option::is_none")]
+    pub overridden: Option<AppliedOverride>,
+}
+
+impl LockedDependency {
+    pub fn new(specifier: String, package: String) -> Self {
+        Self { specifier, package, kind: DependencyKind::Prod, overridden: None }
+    }
+
+    pub fn with_kind(mut self, kind: DependencyKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn effective_specifier(&self) -> &str {
+        match &self.overridden {
+            Some(applied) => &applied.specifier,
+            None => &self.specifier,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub resolved: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, LockedDependency>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub engines: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundled: Vec<String>,
+}
+
+impl LockedPackage {
+    pub fn from_package(info: &PackageVersion) -> Self {
+        Self {
+            name: info.name.clone(),
+            version: info.version.clone(),
+            resolved: info.dist.tarball.clone(),
+            integrity: info.dist.integrity.clone().or_else(|| {
+                Integrity::from_shasum(&info.dist.shasum).map(|integrity| integrity.to_string())
+            }),
+            dependencies: BTreeMap::new(),
+            engines: ENGINES
+                .iter()
+                .filter_map(|engine| {
+                    let range = info.engines.as_ref()?.get(engine)?;
+                    Some((engine.to_string(), range.to_string()))
+                })
+                .collect(),
+            deprecated: info
+                .deprecated
+                .as_ref()
+                .and_then(DeprecatedField::message)
+                .map(str::to_string),
+            bundled: info.bundled(),
+        }
+    }
+
+    pub fn to_package_version(&self) -> PackageVersion {
+        PackageVersion {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            dist: DistInfo {
+                tarball: self.resolved.clone(),
+                integrity: self.integrity.clone(),
+                ..Default::default()
+            },
+            engines: (!self.engines.is_empty())
+                .then(|| EnginesField::Map(self.engines.clone().into_iter().collect())),
+            deprecated: self.deprecated.clone().map(DeprecatedField::Text),
+            bundle_dependencies: (!self.bundled.is_empty())
+                .then(|| BundledField::Seq(self.bundled.clone())),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Lockfile {
+    #[serde(rename = "lockfileVersion")]
+    pub lockfile_version: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, LockedDependency>,
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            lockfile_version: LOCKFILE_VERSION,
+            overrides: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            packages: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    pub fn read(path: &Path) -> Option<Self> {
+        let bytes = read(path).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut data = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        data.push('\n');
+        write(path, data)
+    }
+
+    pub fn matches(&self, manifest: &Manifest) -> bool {
+        let dependencies = manifest.all_dependencies();
+        let declared: Vec<_> = self
+            .dependencies
+            .iter()
+            .filter(|(name, dep)| {
+                dep.kind != DependencyKind::Peer || dependencies.contains_key(*name)
+            })
+            .collect();
+
+        declared.len() == dependencies.len()
+            && declared.into_iter().all(|(name, dep)| {
+                dependencies.get(name) == Some(&dep.specifier)
+                    && dep.kind == manifest.kind(name)
+                    && self.packages.contains_key(&dep.package)
+            })
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &LockedDependency> {
+        self.dependencies
+            .values()
+            .chain(self.packages.values().flat_map(|pkg| pkg.dependencies.values()))
+    }
+
+    pub fn edges_mut(&mut self) -> impl Iterator<Item = &mut LockedDependency> {
+        self.dependencies
+            .values_mut()
+            .chain(self.packages.values_mut().flat_map(|pkg| pkg.dependencies.values_mut()))
+    }
+
+    pub fn reachable(&self, omit: &[DependencyKind]) -> HashSet<String> {
+        let included = |dep: &&LockedDependency| !omit.contains(&dep.kind);
+
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<&str> =
+            self.dependencies.values().filter(included).map(|d| d.package.as_str()).collect();
+
+        while let Some(key) = stack.pop() {
+            if !reachable.insert(key.to_string()) {
+                continue;
+            }
+            if let Some(pkg) = self.packages.get(key) {
+                stack
+                    .extend(pkg.dependencies.values().filter(included).map(|d| d.package.as_str()));
+            }
+        }
+
+        reachable
+    }
+
+    pub fn prune(&mut self) -> Vec<String> {
+        let reachable = self.reachable(&[]);
+
+        let removed: Vec<String> =
+            self.packages.keys().filter(|key| !reachable.contains(*key)).cloned().collect();
+        for key in &removed {
+            self.packages.remove(key);
+        }
+
+        removed
+    }
+
+    pub fn package_versions(&self) -> Vec<PackageVersion> {
+        unique_versions(self.packages.iter())
+    }
+
+    pub fn package_versions_without(&self, omit: &[DependencyKind]) -> Vec<PackageVersion> {
+        let reachable = self.reachable(omit);
+        unique_versions(self.packages.iter().filter(|(key, _)| reachable.contains(*key)))
+    }
+}
+
+fn unique_versions<'a>(
+    packages: impl Iterator<Item = (&'a String, &'a LockedPackage)>,
+) -> Vec<PackageVersion> {
+    let packages: BTreeMap<&str, &LockedPackage> =
+        packages.map(|(key, pkg)| (package_key(key), pkg)).collect();
+    packages.into_values().map(LockedPackage::to_package_version).collect()
+}
+
+pub fn package_key(key: &str) -> &str {
+    key.split_once('(').map_or(key, |(key, _)| key)
+}
+
+pub fn package_name(key: &str) -> &str {
+    split_name_spec(package_key(key)).0
+}
+
+pub fn package_version(key: &str) -> &str {
+    split_name_spec(package_key(key)).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(json: &str) -> Manifest {
+        serde_json::from_str(json).expect("valid manifest")
+    }
+
+    fn lockfile_with_peer() -> Lockfile {
+        let mut lockfile = Lockfile::default();
+        for (name, spec, key, kind) in [
+            ("plugin", "^1.0.0", "plugin@1.0.0", DependencyKind::Prod),
+            ("host", "^2.0.0", "host@2.1.0", DependencyKind::Peer),
+        ] {
+            lockfile.dependencies.insert(
+                name.to_string(),
+                LockedDependency::new(spec.to_string(), key.to_string()).with_kind(kind),
+            );
+            let (name, version) = key.split_once('@').unwrap();
+            lockfile.packages.insert(
+                key.to_string(),
+                LockedPackage {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        lockfile
+    }
+
+    #[test]
+    fn auto_installed_peers_do_not_invalidate_the_lockfile() {
+        let lockfile = lockfile_with_peer();
+        assert!(lockfile.matches(&manifest(r#"{"dependencies": {"plugin": "^1.0.0"}}"#)));
+    }
+
+    #[test]
+    fn declaring_an_auto_installed_peer_invalidates_the_lockfile() {
+        let lockfile = lockfile_with_peer();
+        let manifest = manifest(r#"{"dependencies": {"plugin": "^1.0.0", "host": "^2.0.0"}}"#);
+        assert!(!lockfile.matches(&manifest));
+    }
+
+    #[test]
+    fn changed_specifiers_invalidate_the_lockfile() {
+        let lockfile = lockfile_with_peer();
+        assert!(!lockfile.matches(&manifest(r#"{"dependencies": {"plugin": "^2.0.0"}}"#)));
+        assert!(!lockfile.matches(&manifest(r#"{"devDependencies": {"plugin": "^1.0.0"}}"#)));
+    }
+}
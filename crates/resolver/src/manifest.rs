@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::fs::read;
+use std::path::Path;
+
+use client::versions::RequestPackage;
+
+use crate::lockfile::DependencyKind;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    #[serde(rename = "devDependencies", default)]
+    pub dev_dependencies: BTreeMap<String, String>,
+    #[serde(rename = "optionalDependencies", default)]
+    pub optional_dependencies: BTreeMap<String, String>,
+    #[serde(rename = "peerDependencies", default)]
+    pub peer_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    pub overrides: Option<serde_json::Value>,
+    #[serde(default)]
+    pub resolutions: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> Option<Self> {
+        let bytes = read(path).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn root_name(&self) -> &str {
+        self.name.as_deref().unwrap_or("project")
+    }
+
+    pub fn all_dependencies(&self) -> BTreeMap<String, String> {
+        let mut deps = BTreeMap::new();
+        for map in [&self.dev_dependencies, &self.dependencies, &self.optional_dependencies] {
+            deps.extend(map.iter().map(|(name, spec)| (name.clone(), spec.clone())));
+        }
+        deps
+    }
+
+    pub fn kind(&self, name: &str) -> DependencyKind {
+        if self.optional_dependencies.contains_key(name) {
+            DependencyKind::Optional
+        } else if self.dependencies.contains_key(name) {
+            DependencyKind::Prod
+        } else if self.dev_dependencies.contains_key(name) {
+            DependencyKind::Dev
+        } else {
+            DependencyKind::Prod
+        }
+    }
+
+    pub fn requests(&self) -> Vec<RequestPackage> {
+        self.all_dependencies()
+            .into_iter()
+            .map(|(name, spec)| RequestPackage { name, version: Some(spec) })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(json: &str) -> Manifest {
+        serde_json::from_str(json).expect("valid manifest")
+    }
+
+    #[test]
+    fn optional_dependencies_take_precedence() {
+        let manifest = manifest(
+            r#"{
+                "dependencies": {"fsevents": "^1.0.0", "lodash": "^4.0.0"},
+                "optionalDependencies": {"fsevents": "^2.0.0"}
+            }"#,
+        );
+
+        assert_eq!(manifest.kind("fsevents"), DependencyKind::Optional);
+        assert_eq!(manifest.kind("lodash"), DependencyKind::Prod);
+        assert_eq!(manifest.all_dependencies()["fsevents"], "^2.0.0");
+    }
+
+    #[test]
+    fn prod_dependencies_take_precedence_over_dev() {
+        let manifest = manifest(
+            r#"{"dependencies": {"a": "^1.0.0"}, "devDependencies": {"a": "^2.0.0", "b": "1"}}"#,
+        );
+
+        assert_eq!(manifest.kind("a"), DependencyKind::Prod);
+        assert_eq!(manifest.kind("b"), DependencyKind::Dev);
+        assert_eq!(manifest.all_dependencies()["a"], "^1.0.0");
+    }
+}
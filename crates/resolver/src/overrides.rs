@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use utils::logger::*;
+
+use crate::lockfile::package_key;
+use crate::manifest::Manifest;
+use crate::semver::{Range, SemVer};
+use crate::specifier::split_name_spec;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AppliedOverride {
+    pub selector: String,
+    pub specifier: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    pub name: String,
+    pub range: Option<String>,
+    pub direct: bool,
+}
+
+impl Selector {
+    fn parse(s: &str, direct: bool) -> Self {
+        let (name, range) = split_name_spec(s);
+        let range = (!range.is_empty()).then(|| range.to_string());
+        Self { name: name.to_string(), range, direct }
+    }
+
+    pub fn matches_key(&self, key: &str) -> bool {
+        let (name, version) = split_name_spec(package_key(key));
+        name == self.name && self.matches_version(version)
+    }
+
+    pub fn matches_version(&self, version: &str) -> bool {
+        let Some(range) = &self.range else {
+            return true;
+        };
+
+        match (Range::parse(range), SemVer::parse(version)) {
+            (Some(range), Some(version)) => range.satisfies(&version),
+            _ => range == version,
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(range) = &self.range {
+            write!(f, "@{range}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Override {
+    pub selector: String,
+    pub path: Vec<Selector>,
+    pub specifier: String,
+}
+
+impl Override {
+    fn new(path: Vec<Selector>, specifier: String) -> Self {
+        let mut selector = String::new();
+        for (i, step) in path.iter().enumerate() {
+            match (i, step.direct) {
+                (0, _) => {}
+                (_, true) => selector.push('>'),
+                (_, false) => selector.push_str(">**>"),
+            }
+            selector.push_str(&step.to_string());
+        }
+        Self { selector, path, specifier }
+    }
+
+    pub fn target(&self) -> Option<&Selector> {
+        self.path.last()
+    }
+
+    fn progress(&self, ancestors: &[String]) -> BTreeSet<usize> {
+        let parents = self.path.len().saturating_sub(1);
+        let mut live = BTreeSet::from([0]);
+
+        for key in ancestors {
+            let mut next = BTreeSet::from([0]);
+            for &matched in &live {
+                if matched < parents && self.path[matched].matches_key(key) {
+                    next.insert(matched + 1);
+                }
+                if matched > 0 && !self.path[matched].direct {
+                    next.insert(matched);
+                }
+            }
+            live = next;
+        }
+
+        live
+    }
+
+    fn applies(&self, ancestors: &[String], name: &str) -> bool {
+        let Some(target) = self.target() else {
+            return false;
+        };
+
+        target.name == name && self.progress(ancestors).contains(&(self.path.len() - 1))
+    }
+
+    pub fn applied(&self) -> AppliedOverride {
+        AppliedOverride { selector: self.selector.clone(), specifier: self.specifier.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    rules: Vec<Override>,
+}
+
+impl Overrides {
+    pub fn from_manifest(manifest: &Manifest) -> Self {
+        let mut raw = Vec::new();
+
+        if let Some(overrides) = &manifest.overrides {
+            flatten(&mut raw, &mut Vec::new(), overrides);
+        }
+        for (key, spec) in &manifest.resolutions {
+            raw.push((yarn_path(key), spec.clone()));
+        }
+
+        let mut rules: Vec<Override> = Vec::new();
+        for (path, spec) in raw {
+            if path.is_empty() {
+                continue;
+            }
+
+            let rule = Override::new(path, String::new());
+            let Some(specifier) = resolve_reference(manifest, &spec) else {
+                error(
+                    format!("override {} references unknown dependency {spec}", rule.selector),
+                    false,
+                );
+                continue;
+            };
+
+            rules.retain(|existing| existing.selector != rule.selector);
+            rules.push(Override { specifier, ..rule });
+        }
+
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        self.rules.iter().map(|rule| (rule.selector.clone(), rule.specifier.clone())).collect()
+    }
+
+    pub fn find(&self, ancestors: &[String], name: &str) -> Option<&Override> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.applies(ancestors, name))
+            .max_by_key(|rule| rule.path.len())
+    }
+
+    pub fn context(&self, ancestors: &[String]) -> Option<String> {
+        let states: Vec<String> = self
+            .rules
+            .iter()
+            .flat_map(|rule| {
+                rule.progress(ancestors)
+                    .into_iter()
+                    .filter(|matched| *matched > 0)
+                    .map(|matched| format!("{}:{matched}", rule.selector))
+            })
+            .collect();
+
+        (!states.is_empty()).then(|| states.join(","))
+    }
+}
+
+fn flatten(out: &mut Vec<(Vec<Selector>, String)>, prefix: &mut Vec<Selector>, value: &Value) {
+    let Some(object) = value.as_object() else {
+        return;
+    };
+
+    for (key, value) in object {
+        match value {
+            Value::String(spec) if key == "." => out.push((prefix.clone(), spec.clone())),
+            Value::String(spec) => {
+                let mut path = prefix.clone();
+                path.extend(split_selector(key));
+                out.push((path, spec.clone()));
+            }
+            Value::Object(_) => {
+                let len = prefix.len();
+                prefix.extend(split_selector(key));
+                flatten(out, prefix, value);
+                prefix.truncate(len);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn split_selector(key: &str) -> Vec<Selector> {
+    let bytes = key.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    for (i, &b) in bytes.iter().enumerate() {
+        let splits = b == b'>'
+            && i > 0
+            && (bytes[i - 1].is_ascii_alphanumeric()
+                || matches!(bytes[i - 1], b'-' | b'_' | b'.' | b'*'))
+            && bytes.get(i + 1).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'@');
+        if splits {
+            parts.push(key[start..i].trim());
+            start = i + 1;
+        }
+    }
+    parts.push(key[start..].trim());
+
+    parts.iter().enumerate().map(|(i, part)| Selector::parse(part, i > 0)).collect()
+}
+
+fn yarn_path(key: &str) -> Vec<Selector> {
+    let mut path = Vec::new();
+    let mut direct = false;
+    let mut parts = key.split('/').filter(|part| !part.is_empty());
+
+    while let Some(part) = parts.next() {
+        if part == "**" {
+            direct = false;
+            continue;
+        }
+
+        let name = match part.starts_with('@') {
+            true => format!("{part}/{}", parts.next().unwrap_or_default()),
+            false => part.to_string(),
+        };
+        path.push(Selector::parse(&name, direct && !path.is_empty()));
+        direct = true;
+    }
+
+    path
+}
+
+fn resolve_reference(manifest: &Manifest, spec: &str) -> Option<String> {
+    let Some(name) = spec.strip_prefix('$') else {
+        return Some(spec.to_string());
+    };
+
+    manifest.all_dependencies().remove(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(json: &str) -> Overrides {
+        let manifest: Manifest = serde_json::from_str(json).expect("valid manifest");
+        Overrides::from_manifest(&manifest)
+    }
+
+    fn ancestors(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|key| key.to_string()).collect()
+    }
+
+    fn specifier<'a>(overrides: &'a Overrides, keys: &[&str], name: &str) -> Option<&'a str> {
+        overrides.find(&ancestors(keys), name).map(|rule| rule.specifier.as_str())
+    }
+
+    #[test]
+    fn nested_objects_match_at_any_depth() {
+        let overrides = overrides(r#"{"overrides": {"a": {"b": "2.0.0"}}}"#);
+        assert_eq!(overrides.to_map().keys().collect::<Vec<_>>(), ["a>**>b"]);
+        assert_eq!(specifier(&overrides, &["a@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["a@1.0.0", "m@1.0.0", "n@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["m@1.0.0"], "b"), None);
+    }
+
+    #[test]
+    fn nested_objects_keep_every_ancestor() {
+        let overrides = overrides(r#"{"overrides": {"x": {"a": {"b": "2.0.0"}}}}"#);
+        assert_eq!(specifier(&overrides, &["x@1.0.0", "a@1.0.0", "m@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["x@1.0.0", "m@1.0.0"], "b"), None);
+        assert_eq!(specifier(&overrides, &["a@1.0.0", "m@1.0.0"], "b"), None);
+    }
+
+    #[test]
+    fn chained_selectors_match_direct_children_only() {
+        let overrides = overrides(r#"{"overrides": {"a>b": "2.0.0"}}"#);
+        assert_eq!(specifier(&overrides, &["m@1.0.0", "a@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["a@1.0.0", "m@1.0.0"], "b"), None);
+    }
+
+    #[test]
+    fn selector_ranges_limit_matching_parents() {
+        let overrides = overrides(r#"{"overrides": {"a@^1.0.0": {"b": "2.0.0"}}}"#);
+        assert_eq!(specifier(&overrides, &["a@1.4.0(x>**>b:1)", "m@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["a@2.0.0", "m@1.0.0"], "b"), None);
+    }
+
+    #[test]
+    fn yarn_globstar_matches_at_any_depth() {
+        let overrides = overrides(r#"{"resolutions": {"a/**/b": "2.0.0", "x/b": "3.0.0"}}"#);
+        assert_eq!(specifier(&overrides, &["a@1.0.0", "m@1.0.0", "n@1.0.0"], "b"), Some("2.0.0"));
+        assert_eq!(specifier(&overrides, &["x@1.0.0"], "b"), Some("3.0.0"));
+        assert_eq!(specifier(&overrides, &["x@1.0.0", "m@1.0.0"], "b"), None);
+    }
+
+    #[test]
+    fn deeper_overrides_win() {
+        let overrides = overrides(r#"{"overrides": {"b": "1.5.0", "a": {"b": "2.0.0"}}}"#);
+        assert_eq!(specifier(&overrides, &["m@1.0.0"], "b"), Some("1.5.0"));
+        assert_eq!(specifier(&overrides, &["a@1.0.0", "m@1.0.0"], "b"), Some("2.0.0"));
+    }
+
+    #[test]
+    fn context_tracks_partially_matched_overrides() {
+        let overrides = overrides(r#"{"overrides": {"x": {"a": {"b": "2.0.0"}}}}"#);
+        assert_eq!(overrides.context(&ancestors(&["m@1.0.0"])), None);
+        assert_eq!(overrides.context(&ancestors(&["x@1.0.0"])).as_deref(), Some("x>**>a>**>b:1"));
+        assert_eq!(
+            overrides.context(&ancestors(&["x@1.0.0", "a@1.0.0"])).as_deref(),
+            Some("x>**>a>**>b:1,x>**>a>**>b:2")
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use utils::logger::*;
+
+#[derive(Debug, Clone)]
+pub struct PeerIssue {
+    pub dependent: String,
+    pub name: String,
+    pub range: String,
+    pub provided: Option<String>,
+}
+
+pub fn report(issues: &[PeerIssue], strict: bool) {
+    if issues.is_empty() {
+        return;
+    }
+
+    let mut grouped: BTreeMap<&str, Vec<&PeerIssue>> = BTreeMap::new();
+    for issue in issues {
+        grouped.entry(&issue.name).or_default().push(issue);
+    }
+
+    let header = format!("Unmet peer dependencies ({})", issues.len());
+    match strict {
+        true => error(header, false),
+        false => warn(header, false),
+    }
+
+    for (name, issues) in grouped {
+        sub_warn(name, false);
+        for issue in issues {
+            let found = match &issue.provided {
+                Some(provided) => format!("found {provided}"),
+                None => "missing".to_string(),
+            };
+            sub_sub_log(
+                format!("{} wants {name}@{} ({found})", issue.dependent, issue.range),
+                false,
+                strict,
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::env::consts::{ARCH, OS};
+use std::fmt;
+
+use client::registry::{PackageVersion, PlatformField};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    pub os: String,
+    pub cpu: String,
+    pub libc: Option<String>,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        let os = match OS {
+            "macos" => "darwin",
+            "windows" => "win32",
+            os => os,
+        };
+
+        let cpu = match ARCH {
+            "x86_64" => "x64",
+            "x86" => "ia32",
+            "aarch64" => "arm64",
+            "powerpc64" => "ppc64",
+            "s390x" => "s390x",
+            arch => arch,
+        };
+
+        let libc = match (os, cfg!(target_env = "musl")) {
+            ("linux", true) => Some("musl".to_string()),
+            ("linux", false) => Some("glibc".to_string()),
+            _ => None,
+        };
+
+        Self { os: os.to_string(), cpu: cpu.to_string(), libc }
+    }
+
+    pub fn target(os: Option<String>, cpu: Option<String>) -> Self {
+        let current = Self::current();
+
+        let libc = match os.as_deref() {
+            None | Some("linux") => current.libc,
+            Some(_) => None,
+        };
+
+        Self { os: os.unwrap_or(current.os), cpu: cpu.unwrap_or(current.cpu), libc }
+    }
+
+    pub fn supports(&self, info: &PackageVersion) -> bool {
+        allowed(&info.os, Some(&self.os))
+            && allowed(&info.cpu, Some(&self.cpu))
+            && allowed(&info.libc, self.libc.as_deref())
+    }
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.os, self.cpu)?;
+        if let Some(libc) = &self.libc {
+            write!(f, "-{libc}")?;
+        }
+        Ok(())
+    }
+}
+
+fn allowed(field: &Option<PlatformField>, value: Option<&str>) -> bool {
+    let (Some(field), Some(value)) = (field, value) else {
+        return true;
+    };
+
+    let values = field.values();
+    if values.iter().any(|v| v.strip_prefix('!') == Some(value)) {
+        return false;
+    }
+
+    let wanted: Vec<_> = values.iter().filter(|v| !v.starts_with('!')).collect();
+    wanted.is_empty() || wanted.iter().any(|v| **v == value || **v == "any")
+}
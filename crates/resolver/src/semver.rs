@@ -1,39 +1,144 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use client::config::CONFIG;
+use client::registry::parse_timestamp;
+use client::versions::PackageMetadata;
+use once_cell::sync::Lazy;
+use utils::logger::*;
+
 use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+use crate::engines;
+
+const MAX_LENGTH: usize = 256;
+const MAX_SAFE_INTEGER: u64 = 9_007_199_254_740_991;
+
+#[derive(Debug, Clone)]
 pub struct SemVer {
     pub major: u64,
     pub minor: u64,
     pub patch: u64,
     pub pre: Option<String>,
+    pub build: Option<String>,
 }
 
 impl SemVer {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self { major, minor, patch, pre: None, build: None }
+    }
+
     pub fn parse(s: &str) -> Option<Self> {
-        if s.is_empty() {
+        let s = s.trim();
+        if s.is_empty() || s.len() > MAX_LENGTH {
             return None;
         }
 
-        let (main, pre) = if let Some(dash_pos) = s.find('-') {
-            let (main_part, pre_part) = s.split_at(dash_pos);
-            (main_part, Some(pre_part[1..].to_string()))
-        } else {
-            (s, None)
+        let s = s.strip_prefix('v').unwrap_or(s);
+
+        let (s, build) = match s.split_once('+') {
+            Some((main, build)) if valid_identifiers(build, true) => {
+                (main, Some(build.to_string()))
+            }
+            Some(_) => return None,
+            None => (s, None),
+        };
+
+        let (main, pre) = match s.split_once('-') {
+            Some((main, pre)) if valid_identifiers(pre, false) => (main, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (s, None),
         };
 
         let mut parts = main.splitn(3, '.');
-        let major = parts.next()?.parse().ok()?;
-        let minor = parts.next()?.parse().ok()?;
-        let patch = parts.next()?.parse().ok()?;
+        let major = parse_numeric(parts.next()?)?;
+        let minor = parse_numeric(parts.next()?)?;
+        let patch = parse_numeric(parts.next()?)?;
 
-        if parts.next().is_some() {
-            return None;
+        Some(Self { major, minor, patch, pre, build })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some()
+    }
+
+    fn same_tuple(&self, other: &Self) -> bool {
+        self.major == other.major && self.minor == other.minor && self.patch == other.patch
+    }
+
+    fn with_pre(mut self, pre: &str) -> Self {
+        self.pre = Some(pre.to_string());
+        self
+    }
+}
+
+fn parse_numeric(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0'))
+    {
+        return None;
+    }
+
+    s.parse().ok().filter(|n| *n <= MAX_SAFE_INTEGER)
+}
+
+fn valid_identifiers(s: &str, allow_leading_zeros: bool) -> bool {
+    s.split('.').all(|id| {
+        let numeric = id.bytes().all(|b| b.is_ascii_digit());
+        !id.is_empty()
+            && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            && (allow_leading_zeros || !numeric || id == "0" || !id.starts_with('0'))
+    })
+}
+
+fn compare_identifier(a: &str, b: &str) -> Ordering {
+    let a_numeric = a.bytes().all(|c| c.is_ascii_digit());
+    let b_numeric = b.bytes().all(|c| c.is_ascii_digit());
+
+    match (a_numeric, b_numeric) {
+        (true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.cmp(b),
+    }
+}
+
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+
+    loop {
+        match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match compare_identifier(x, y) {
+                Ordering::Equal => continue,
+                ordering => return ordering,
+            },
         }
+    }
+}
 
-        Some(Self { major, minor, patch, pre })
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+        Ok(())
     }
 }
 
+impl PartialEq for SemVer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SemVer {}
+
 impl PartialOrd for SemVer {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -50,76 +155,877 @@ impl Ord for SemVer {
                 (None, None) => Ordering::Equal,
                 (None, Some(_)) => Ordering::Greater,
                 (Some(_), None) => Ordering::Less,
-                (Some(a), Some(b)) => a.cmp(b),
+                (Some(a), Some(b)) => compare_pre(a, b),
             })
     }
 }
 
-fn semver_satisfies(version: &SemVer, range: &str) -> bool {
-    let range = range.trim();
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Op {
+    Eq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Op::Eq => "",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Comparator {
+    pub op: Op,
+    pub version: SemVer,
+}
+
+impl Comparator {
+    pub fn new(op: Op, version: SemVer) -> Self {
+        Self { op, version }
+    }
+
+    pub fn matches(&self, version: &SemVer) -> bool {
+        match self.op {
+            Op::Eq => version == &self.version,
+            Op::Gt => version > &self.version,
+            Op::Gte => version >= &self.version,
+            Op::Lt => version < &self.version,
+            Op::Lte => version <= &self.version,
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op, self.version)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Range {
+    pub sets: Vec<Vec<Comparator>>,
+    pub include_prerelease: bool,
+}
 
-    if range == "latest" || range == "*" {
-        return true;
+impl Range {
+    pub fn any() -> Self {
+        Self { sets: vec![Vec::new()], include_prerelease: false }
     }
 
-    if let Some(exact) = SemVer::parse(range) {
-        return version == &exact;
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::parse_with(s, false)
     }
 
-    if let Some(rest) = range.strip_prefix('^') {
-        if let Some(min) = SemVer::parse(rest) {
-            return version >= &min && version.major == min.major;
+    pub fn parse_with(s: &str, include_prerelease: bool) -> Option<Self> {
+        if s.len() > MAX_LENGTH {
+            return None;
         }
+
+        let mut sets = Vec::new();
+
+        for part in s.split("||") {
+            sets.push(parse_comparator_set(part.trim(), include_prerelease)?);
+        }
+
+        Some(Self { sets, include_prerelease })
+    }
+
+    pub fn satisfies(&self, version: &SemVer) -> bool {
+        self.sets.iter().any(|set| self.test_set(set, version))
+    }
+
+    fn test_set(&self, set: &[Comparator], version: &SemVer) -> bool {
+        if !set.iter().all(|c| c.matches(version)) {
+            return false;
+        }
+
+        if version.is_prerelease() && !self.include_prerelease {
+            return set.iter().any(|c| c.version.is_prerelease() && c.version.same_tuple(version));
+        }
+
+        true
+    }
+}
+
+impl FromStr for Range {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or(())
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, set) in self.sets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" || ")?;
+            }
+            if set.is_empty() {
+                f.write_str("*")?;
+            }
+            for (j, comparator) in set.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{comparator}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RangeOp {
+    Primitive(Op),
+    Caret,
+    Tilde,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Option<String>,
+}
+
+impl Partial {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim_start_matches('=').trim();
+        let s = s.strip_prefix('v').or_else(|| s.strip_prefix('V')).unwrap_or(s);
+
+        if s.is_empty() {
+            return Some(Self::default());
+        }
+
+        let s = match s.split_once('+') {
+            Some((main, build)) if valid_identifiers(build, true) => main,
+            Some(_) => return None,
+            None => s,
+        };
+
+        let (main, pre) = match s.split_once('-') {
+            Some((main, pre)) if valid_identifiers(pre, false) => (main, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (s, None),
+        };
+
+        let mut parts = main.split('.');
+        let major = parse_part(parts.next()?)?;
+        let minor = parts.next().map(parse_part).unwrap_or(Some(None))?;
+        let patch = parts.next().map(parse_part).unwrap_or(Some(None))?;
+
+        if parts.next().is_some() || (pre.is_some() && patch.is_none()) {
+            return None;
+        }
+
+        let minor = major.and(minor);
+        let patch = minor.and(patch);
+
+        Some(Self { major, minor, patch, pre })
+    }
+
+    fn is_any(&self) -> bool {
+        self.major.is_none()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.patch.is_none()
+    }
+
+    fn floor(&self) -> SemVer {
+        let version =
+            SemVer::new(self.major.unwrap_or(0), self.minor.unwrap_or(0), self.patch.unwrap_or(0));
+        match &self.pre {
+            Some(pre) => version.with_pre(pre),
+            None => version,
+        }
+    }
+
+    fn lower_bound(&self, include_prerelease: bool) -> SemVer {
+        if include_prerelease && self.is_partial() {
+            self.floor().with_pre("0")
+        } else {
+            self.floor()
+        }
+    }
+}
+
+fn parse_part(s: &str) -> Option<Option<u64>> {
+    match s {
+        "x" | "X" | "*" => Some(None),
+        _ => parse_numeric(s).map(Some),
+    }
+}
+
+fn parse_comparator_set(s: &str, include_prerelease: bool) -> Option<Vec<Comparator>> {
+    if let Some((from, to)) = split_hyphen(s) {
+        let from = Partial::parse(from)?;
+        let to = Partial::parse(to)?;
+        return Some(hyphen_range(&from, &to, include_prerelease));
+    }
+
+    let mut comparators = Vec::new();
+    for token in tokenize(s) {
+        let (op, rest) = split_op(&token);
+        let partial = Partial::parse(rest)?;
+        comparators.extend(desugar(op, &partial, include_prerelease));
+    }
+
+    Some(comparators)
+}
+
+fn split_hyphen(s: &str) -> Option<(&str, &str)> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    if tokens.len() == 3 && tokens[1] == "-" {
+        return Some((tokens[0], tokens[2]));
     }
+    None
+}
 
-    if let Some(rest) = range.strip_prefix('~') {
-        if let Some(min) = SemVer::parse(rest) {
-            return version >= &min && version.major == min.major && version.minor == min.minor;
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut pending_op = String::new();
+
+    for word in s.split_whitespace() {
+        if word.chars().all(|c| matches!(c, '<' | '>' | '=' | '^' | '~')) {
+            pending_op.push_str(word);
+            continue;
         }
+        tokens.push(format!("{}{word}", std::mem::take(&mut pending_op)));
     }
 
-    if let Some(stripped) = range.strip_prefix(">=") {
-        if let Some(min) = SemVer::parse(stripped) {
-            return version >= &min;
+    if !pending_op.is_empty() {
+        tokens.push(pending_op);
+    }
+
+    tokens
+}
+
+fn split_op(token: &str) -> (RangeOp, &str) {
+    const OPS: [(&str, RangeOp); 8] = [
+        ("~>", RangeOp::Tilde),
+        (">=", RangeOp::Primitive(Op::Gte)),
+        ("<=", RangeOp::Primitive(Op::Lte)),
+        (">", RangeOp::Primitive(Op::Gt)),
+        ("<", RangeOp::Primitive(Op::Lt)),
+        ("=", RangeOp::Primitive(Op::Eq)),
+        ("^", RangeOp::Caret),
+        ("~", RangeOp::Tilde),
+    ];
+
+    for (prefix, op) in OPS {
+        if let Some(rest) = token.strip_prefix(prefix) {
+            return (op, rest);
         }
     }
 
-    if let Some(stripped) = range.strip_prefix('>') {
-        if let Some(min) = SemVer::parse(stripped) {
-            return version > &min;
+    (RangeOp::Primitive(Op::Eq), token)
+}
+
+fn upper(major: u64, minor: u64, patch: u64) -> Comparator {
+    Comparator::new(Op::Lt, SemVer::new(major, minor, patch).with_pre("0"))
+}
+
+fn desugar(op: RangeOp, p: &Partial, include_prerelease: bool) -> Vec<Comparator> {
+    match op {
+        RangeOp::Caret => {
+            let floor = Comparator::new(Op::Gte, p.lower_bound(include_prerelease));
+            desugar_caret(p, floor)
         }
+        RangeOp::Tilde => {
+            let floor = Comparator::new(Op::Gte, p.floor());
+            desugar_tilde(p, floor)
+        }
+        RangeOp::Primitive(op) => desugar_primitive(op, p, include_prerelease),
     }
+}
 
-    if let Some(stripped) = range.strip_prefix("<=") {
-        if let Some(max) = SemVer::parse(stripped) {
-            return version <= &max;
+fn desugar_caret(p: &Partial, floor: Comparator) -> Vec<Comparator> {
+    match (p.major, p.minor, p.patch) {
+        (None, ..) => Vec::new(),
+        (Some(0), Some(0), Some(patch)) => vec![floor, upper(0, 0, patch + 1)],
+        (Some(0), Some(minor), _) => vec![floor, upper(0, minor + 1, 0)],
+        (Some(major), ..) => vec![floor, upper(major + 1, 0, 0)],
+    }
+}
+
+fn desugar_tilde(p: &Partial, floor: Comparator) -> Vec<Comparator> {
+    match (p.major, p.minor) {
+        (None, _) => Vec::new(),
+        (Some(major), None) => vec![floor, upper(major + 1, 0, 0)],
+        (Some(major), Some(minor)) => vec![floor, upper(major, minor + 1, 0)],
+    }
+}
+
+fn desugar_primitive(op: Op, p: &Partial, include_prerelease: bool) -> Vec<Comparator> {
+    if p.is_any() {
+        return match op {
+            Op::Gt | Op::Lt => vec![Comparator::new(Op::Lt, SemVer::new(0, 0, 0).with_pre("0"))],
+            _ => Vec::new(),
+        };
+    }
+
+    let major = p.major.unwrap_or(0);
+    let exact = p.patch.is_some();
+
+    let floor = p.lower_bound(include_prerelease);
+
+    match (op, p.minor) {
+        (_, _) if exact => vec![Comparator::new(op, p.floor())],
+        (Op::Eq, None) => vec![Comparator::new(Op::Gte, floor), upper(major + 1, 0, 0)],
+        (Op::Eq, Some(minor)) => vec![Comparator::new(Op::Gte, floor), upper(major, minor + 1, 0)],
+        (Op::Gt, None) => vec![Comparator::new(Op::Gte, SemVer::new(major + 1, 0, 0))],
+        (Op::Gt, Some(minor)) => {
+            vec![Comparator::new(Op::Gte, SemVer::new(major, minor + 1, 0))]
         }
+        (Op::Gte, _) => vec![Comparator::new(Op::Gte, floor)],
+        (Op::Lt, _) => vec![Comparator::new(Op::Lt, p.floor().with_pre("0"))],
+        (Op::Lte, None) => vec![upper(major + 1, 0, 0)],
+        (Op::Lte, Some(minor)) => vec![upper(major, minor + 1, 0)],
+    }
+}
+
+fn hyphen_range(from: &Partial, to: &Partial, include_prerelease: bool) -> Vec<Comparator> {
+    let mut comparators = Vec::new();
+
+    if !from.is_any() {
+        comparators.push(Comparator::new(Op::Gte, from.lower_bound(include_prerelease)));
     }
 
-    if let Some(stripped) = range.strip_prefix('<') {
-        if let Some(max) = SemVer::parse(stripped) {
-            return version < &max;
+    match (to.major, to.minor, to.patch) {
+        (None, ..) => {}
+        (Some(major), None, _) => comparators.push(upper(major + 1, 0, 0)),
+        (Some(major), Some(minor), None) => comparators.push(upper(major, minor + 1, 0)),
+        (Some(major), Some(minor), Some(patch)) if include_prerelease && to.pre.is_none() => {
+            comparators.push(upper(major, minor, patch + 1))
         }
+        (Some(_), Some(_), Some(_)) => comparators.push(Comparator::new(Op::Lte, to.floor())),
     }
 
-    false
+    comparators
 }
 
-pub fn select_version(version_req: &str, available: Vec<&str>) -> Option<String> {
-    let mut parsed: Vec<SemVer> = available.iter().filter_map(|v| SemVer::parse(v)).collect();
+static REPORTED_SKIPS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static REPORTED_UNKNOWN: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Default)]
+pub struct SelectOptions {
+    pub include_prerelease: bool,
+    pub before: Option<DateTime<Utc>>,
+    pub minimum_release_age: Option<TimeDelta>,
+    pub release_age_exclude: Vec<String>,
+    pub node: Option<SemVer>,
+}
 
-    parsed.sort_by(|a, b| b.cmp(a));
+impl SelectOptions {
+    pub fn from_config() -> Self {
+        let before = CONFIG.get("before").and_then(|value| {
+            let parsed = parse_timestamp(value);
+            if parsed.is_none() {
+                warn(format!("ignoring invalid 'before' setting: {value}"), false);
+            }
+            parsed
+        });
 
-    for ver in parsed {
-        if semver_satisfies(&ver, version_req) {
-            let base = format!("{}.{}.{}", ver.major, ver.minor, ver.patch);
-            if let Some(pre) = &ver.pre {
-                return Some(format!("{base}-{pre}"));
-            } else {
-                return Some(base);
+        let minimum_release_age = CONFIG.get("minimum-release-age").and_then(|value| {
+            let hours = value.parse::<i64>().ok().filter(|hours| *hours >= 0);
+            if hours.is_none() {
+                warn(format!("ignoring invalid 'minimum-release-age' setting: {value}"), false);
             }
+            hours.and_then(TimeDelta::try_hours)
+        });
+
+        let release_age_exclude = CONFIG
+            .get("minimum-release-age-exclude")
+            .map(|value| {
+                value
+                    .split([',', ' '])
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let node = match CONFIG.get_bool("prefer-engine-compatible") {
+            Some(true) => engines::node_version(),
+            _ => None,
+        };
+
+        Self { before, minimum_release_age, release_age_exclude, node, ..Default::default() }
+    }
+
+    pub fn needs_publish_times(&self) -> bool {
+        self.before.is_some() || self.minimum_release_age.is_some()
+    }
+
+    fn allows(&self, version: &str, metadata: &PackageMetadata) -> bool {
+        self.published_before(version, metadata)
+            && !self.too_young(version, metadata)
+            && self.engine_compatible(version, metadata)
+    }
+
+    fn engine_compatible(&self, version: &str, metadata: &PackageMetadata) -> bool {
+        match (&self.node, metadata.get(version)) {
+            (Some(node), Some(info)) => engines::supports(info, node),
+            _ => true,
         }
     }
 
-    None
+    fn published_before(&self, version: &str, metadata: &PackageMetadata) -> bool {
+        let Some(before) = self.before else {
+            return true;
+        };
+        published(version, metadata).is_some_and(|published| published <= before)
+    }
+
+    fn too_young(&self, version: &str, metadata: &PackageMetadata) -> bool {
+        let Some(minimum) = self.minimum_release_age else {
+            return false;
+        };
+        if self.is_excluded(&metadata.name) {
+            return false;
+        }
+        let Some(published) = published(version, metadata) else {
+            return true;
+        };
+
+        let age = Utc::now() - published;
+        if age >= minimum {
+            return false;
+        }
+        report_skip(metadata, version, age, self);
+        true
+    }
+
+    fn is_excluded(&self, name: &str) -> bool {
+        self.release_age_exclude.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+    }
+}
+
+pub fn satisfies(version: &str, range: &str) -> bool {
+    match (SemVer::parse(version), Range::parse(range)) {
+        (Some(version), Some(range)) => range.satisfies(&version),
+        _ => false,
+    }
+}
+
+pub fn select_version(
+    version_req: &str,
+    metadata: &PackageMetadata,
+    options: &SelectOptions,
+) -> Option<String> {
+    matching_versions(version_req, metadata, options).into_iter().next()
+}
+
+pub fn matching_versions(
+    version_req: &str,
+    metadata: &PackageMetadata,
+    options: &SelectOptions,
+) -> Vec<String> {
+    let version_req = version_req.trim();
+
+    let Some(range) = Range::parse_with(version_req, options.include_prerelease) else {
+        return select_tag(version_req, metadata, options).into_iter().collect();
+    };
+
+    let mut parsed: Vec<(SemVer, &str)> = metadata
+        .versions
+        .iter()
+        .filter_map(|(v, _)| SemVer::parse(v).map(|ver| (ver, v.as_str())))
+        .filter(|(ver, v)| range.satisfies(ver) && options.published_before(v, metadata))
+        .collect();
+
+    parsed.retain(|(_, v)| !options.too_young(v, metadata));
+
+    parsed.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut matching: Vec<String> = parsed.into_iter().map(|(_, v)| v.to_string()).collect();
+
+    if let Some(latest) = metadata.dist_tags.get("latest")
+        && let Some(pos) = matching.iter().position(|v| v == latest)
+    {
+        let latest = matching.remove(pos);
+        matching.insert(0, latest);
+    }
+
+    if options.node.is_some() {
+        matching.sort_by_key(|v| !options.engine_compatible(v, metadata));
+    }
+
+    matching
+}
+
+fn published(version: &str, metadata: &PackageMetadata) -> Option<DateTime<Utc>> {
+    let published = metadata.published(version);
+    if published.is_none()
+        && let Ok(mut reported) = REPORTED_UNKNOWN.lock()
+        && reported.insert(metadata.name.clone())
+    {
+        warn(
+            format!(
+                "Skipping versions of {} without a publish time in the registry metadata",
+                metadata.name
+            ),
+            false,
+        );
+    }
+    published
+}
+
+fn report_skip(metadata: &PackageMetadata, version: &str, age: TimeDelta, options: &SelectOptions) {
+    let key = format!("{}@{version}", metadata.name);
+    let Ok(mut reported) = REPORTED_SKIPS.lock() else {
+        return;
+    };
+    if !reported.insert(key.clone()) {
+        return;
+    }
+
+    let minimum = options.minimum_release_age.map(|m| m.num_hours()).unwrap_or_default();
+    warn(
+        format!(
+            "Skipping {key}: published {}h ago, minimum release age is {minimum}h",
+            age.num_hours()
+        ),
+        false,
+    );
+}
+
+fn select_tag(tag: &str, metadata: &PackageMetadata, options: &SelectOptions) -> Option<String> {
+    match metadata.dist_tags.get(tag) {
+        Some(version) if !options.allows(version, metadata) => {
+            select_version(&format!("<={version}"), metadata, options)
+        }
+        Some(version) => metadata.get(version).map(|_| version.clone()),
+        None if tag == "latest" => select_version("*", metadata, options),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client::registry::PackageVersion;
+
+    const INCLUDED: &[(&str, &str)] = &[
+        ("1.0.0 - 2.0.0", "1.2.3"),
+        ("1.2.3 - 2.3", "2.3.9"),
+        ("1.2 - 2", "2.9.9"),
+        ("1.2.3 - *", "9.9.9"),
+        ("1.0.0", "1.0.0"),
+        ("=1.0.0", "1.0.0"),
+        ("v1.0.0", "1.0.0"),
+        (">=*", "0.2.4"),
+        ("", "1.0.0"),
+        ("*", "1.2.3"),
+        (">=1.0.0", "1.0.0"),
+        (">1.0.0", "1.0.1"),
+        ("<2.0.0", "1.9999.9999"),
+        ("<=2.0.0", "2.0.0"),
+        ("< 2.0.0", "0.2.9"),
+        (">= 1.0.0", "1.0.0"),
+        ("0.1.20 || 1.2.4", "1.2.4"),
+        (">=0.2.3 || <0.0.1", "0.0.0"),
+        (">=0.2.3 || <0.0.1", "0.2.3"),
+        ("||", "1.3.4"),
+        ("2.x.x", "2.1.3"),
+        ("1.2.x", "1.2.3"),
+        ("1.2.x || 2.x", "2.1.3"),
+        ("x", "1.2.3"),
+        ("2.*.*", "2.1.3"),
+        ("1.2.*", "1.2.3"),
+        ("2", "2.1.2"),
+        ("2.3", "2.3.1"),
+        ("~2.4", "2.4.0"),
+        ("~>3.2.1", "3.2.2"),
+        ("~1", "1.2.3"),
+        ("~>1", "1.2.3"),
+        ("~> 1", "1.2.3"),
+        ("~1.0", "1.0.2"),
+        ("~ 1.0", "1.0.2"),
+        ("~ 1.0.3", "1.0.12"),
+        ("~1.2.1 >=1.2.3", "1.2.3"),
+        ("~1.2.1 =1.2.3", "1.2.3"),
+        ("~0.0.1", "0.0.2"),
+        (">=1", "1.0.0"),
+        (">= 1", "1.0.0"),
+        ("<1.2", "1.1.1"),
+        ("< 1.2", "1.1.1"),
+        (">1", "2.0.0"),
+        (">1.2", "1.3.0"),
+        ("<=1.2", "1.2.9"),
+        ("<1", "0.9.9"),
+        ("^1.2.3", "1.8.1"),
+        ("^0.1.2", "0.1.2"),
+        ("^0.1", "0.1.2"),
+        ("^1.2", "1.4.2"),
+        ("^1.2 ^1", "1.4.2"),
+        ("^1.2.3-alpha", "1.2.3-pre"),
+        ("^1.2.0-alpha", "1.2.0-pre"),
+        ("^0.0.1-alpha", "0.0.1-beta"),
+        ("^0.1.1-alpha", "0.1.1-beta"),
+        ("^x", "1.2.3"),
+        ("^0.0.3", "0.0.3"),
+        ("^0", "0.9.9"),
+        ("^0.0", "0.0.9"),
+        ("^1.2.3+build", "1.2.3"),
+        ("^1.2.3+build", "1.3.0"),
+        ("x - 1.0.0", "0.9.7"),
+        ("x - 1.x", "0.9.7"),
+        ("1.0.0 - x", "1.9.7"),
+        ("1.x - x", "1.9.7"),
+        ("<=7.x", "7.9.9"),
+        ("1.2.3-pr.2", "1.2.3-pr.2"),
+        (">1.2.3-alpha.3", "1.2.3-alpha.7"),
+        ("^1.2.3-beta.2", "1.2.3-beta.4"),
+        (">=1.2.3-beta.2 <2", "1.2.3-beta.10"),
+        ("~1.2.3-beta.2", "1.2.3-beta.9"),
+        ("1.2.3+asdf", "1.2.3"),
+        ("1.2.3", "1.2.3+build.5"),
+        ("^1.2.3", "1.2.3+build"),
+        ("=0.7.x", "0.7.2"),
+        ("<=0.7.x", "0.7.2"),
+        (">=0.7.x", "0.7.2"),
+        ("<=0.7.x", "0.6.2"),
+        ("<0.7.x", "0.6.9"),
+        (">1.2.3 <1.2.5", "1.2.4"),
+        (">= 1.2.3 < 1.2.5", "1.2.4"),
+    ];
+
+    const EXCLUDED: &[(&str, &str)] = &[
+        ("1.0.0 - 2.0.0", "2.0.1"),
+        ("1.0.0 - 2.0.0", "0.9.9"),
+        ("1.2.3 - 2.3", "2.4.0"),
+        ("1.2 - 2", "1.1.9"),
+        ("=v1.0.0", "1.0.1"),
+        ("*", "1.2.3-beta"),
+        (">=1.0.0", "0.9.9"),
+        (">1.0.0", "1.0.0"),
+        ("<2.0.0", "2.0.0-beta"),
+        ("0.1.20 || 1.2.4", "1.2.3"),
+        (">=0.2.3 || <0.0.1", "0.0.3"),
+        ("2.x.x", "3.1.3"),
+        ("1.2.x", "1.3.3"),
+        ("2.3", "2.4.1"),
+        ("~2.4", "2.5.0"),
+        ("~>3.2.1", "3.3.0"),
+        ("~1", "2.0.0"),
+        ("~0.0.1", "0.1.0"),
+        ("<1.2", "1.2.0"),
+        (">1", "1.9.9"),
+        (">1.2", "1.2.9"),
+        ("<=1.2", "1.3.0"),
+        ("<1", "1.0.0-beta"),
+        ("^1.2.3", "2.0.0"),
+        ("^1.2.3", "1.2.2"),
+        ("^0.1.2", "0.2.0"),
+        ("^0.1", "0.2.0"),
+        ("^1.2", "1.1.0"),
+        ("^0.0.1-alpha", "0.0.2-beta"),
+        ("^0.0.3", "0.0.4"),
+        ("^0", "1.0.0"),
+        ("^0.0", "0.1.0"),
+        ("<=7.x", "8.0.0"),
+        (">1.2.3-alpha.3", "3.4.5-alpha.9"),
+        (">1.2.3-alpha.3", "1.2.3-alpha.2"),
+        ("^1.2.3", "1.2.4-beta"),
+        ("^1.2.3-beta.2", "1.2.4-beta.4"),
+        ("1.0.0 - 2.0.0", "2.0.0-beta"),
+        ("~1.2.3-beta.2", "1.2.4-beta.1"),
+        (">0.7.x", "0.7.2"),
+        ("2.x.x", "1.1.3"),
+        ("<1", "1.0.0"),
+        (">=1.2", "1.1.9"),
+        (">1.2.3 <1.2.5", "1.2.5"),
+    ];
+
+    fn version(s: &str) -> SemVer {
+        SemVer::parse(s).unwrap_or_else(|| panic!("{s} should parse"))
+    }
+
+    fn range(s: &str) -> Range {
+        Range::parse(s).unwrap_or_else(|| panic!("{s} should parse"))
+    }
+
+    #[test]
+    fn ranges_include_matching_versions() {
+        for (r, v) in INCLUDED {
+            assert!(range(r).satisfies(&version(v)), "{r} should include {v}");
+        }
+    }
+
+    #[test]
+    fn ranges_exclude_other_versions() {
+        for (r, v) in EXCLUDED {
+            assert!(!range(r).satisfies(&version(v)), "{r} should exclude {v}");
+        }
+    }
+
+    #[test]
+    fn desugars_ranges_into_comparators() {
+        let cases = [
+            ("^1.2.3", ">=1.2.3 <2.0.0-0"),
+            ("^0.2.3", ">=0.2.3 <0.3.0-0"),
+            ("^0.0.3", ">=0.0.3 <0.0.4-0"),
+            ("~1.2.3", ">=1.2.3 <1.3.0-0"),
+            ("~1", ">=1.0.0 <2.0.0-0"),
+            ("1.2.3 - 2.3", ">=1.2.3 <2.4.0-0"),
+            ("1.x || >=2.5.0 || 5.0.0 - 7.2.3", ">=1.0.0 <2.0.0-0 || >=2.5.0 || >=5.0.0 <=7.2.3"),
+            (">1.2", ">=1.3.0"),
+            ("<=1.2", "<1.3.0-0"),
+            ("*", "*"),
+            (">=1.2.3-beta.1 <2", ">=1.2.3-beta.1 <2.0.0-0"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(range(input).to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn parses_valid_versions() {
+        let cases = [
+            ("1.2.3", "1.2.3"),
+            ("v1.2.3", "1.2.3"),
+            (" 1.2.3 ", "1.2.3"),
+            ("1.2.3-0a", "1.2.3-0a"),
+            ("1.2.3+build.01", "1.2.3"),
+            ("1.2.3-rc.1+build.01", "1.2.3-rc.1"),
+            ("9007199254740991.0.0", "9007199254740991.0.0"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(version(input).to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        let cases = [
+            "01.2.3",
+            "1.02.3",
+            "1.2.03",
+            "1.2.3-01",
+            "1.2.3-beta.01",
+            "9007199254740992.0.0",
+            "1.2",
+            "1.2.3.4",
+            "a.b.c",
+            "1.2.3-",
+            "1.2.3+",
+            "=1.2.3",
+        ];
+
+        for input in cases {
+            assert!(SemVer::parse(input).is_none(), "{input} should be rejected");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        let cases = [
+            ">01.2.3",
+            "1.2.3-01",
+            "^9007199254740992.0.0",
+            "1.2.3 - 01.0.0",
+            ">=1.2.3 <=1.2.3-",
+            "1.2.3 foo",
+            "blerg",
+            "~1.2.3-01",
+            ">=a.b.c",
+        ];
+
+        for input in cases {
+            assert!(Range::parse(input).is_none(), "{input} should be rejected");
+        }
+    }
+
+    #[test]
+    fn orders_prerelease_identifiers() {
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.2",
+            "1.0.0-alpha.10",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+            "1.1.0",
+            "2.0.0",
+        ];
+
+        for pair in ordered.windows(2) {
+            assert!(version(pair[0]) < version(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+
+        let mut shuffled: Vec<SemVer> = ordered.iter().rev().map(|v| version(v)).collect();
+        shuffled.sort();
+        let sorted: Vec<String> = shuffled.iter().map(SemVer::to_string).collect();
+        assert_eq!(sorted, ordered);
+    }
+
+    #[test]
+    fn ignores_build_metadata_when_comparing() {
+        assert_eq!(version("1.2.3+a"), version("1.2.3+b"));
+        assert!(version("1.0.0-1") < version("1.0.0-a"));
+        assert!(version("1.0.0-a-b") > version("1.0.0-a"));
+    }
+
+    fn metadata_with_times(times: &[(&str, Option<&str>)]) -> PackageMetadata {
+        PackageMetadata {
+            name: "pkg".to_string(),
+            versions: times
+                .iter()
+                .map(|(v, _)| {
+                    (v.to_string(), PackageVersion { version: v.to_string(), ..Default::default() })
+                })
+                .collect(),
+            time: times
+                .iter()
+                .filter_map(|(v, time)| Some((v.to_string(), parse_timestamp((*time)?)?)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn before_skips_versions_without_publish_times() {
+        let metadata = metadata_with_times(&[
+            ("1.0.0", Some("2020-01-01T00:00:00Z")),
+            ("1.1.0", None),
+            ("1.2.0", Some("2022-01-01T00:00:00Z")),
+        ]);
+        let options = SelectOptions { before: parse_timestamp("2021-01-01"), ..Default::default() };
+        assert_eq!(matching_versions("^1.0.0", &metadata, &options), ["1.0.0"]);
+    }
+
+    #[test]
+    fn minimum_release_age_skips_versions_without_publish_times() {
+        let metadata =
+            metadata_with_times(&[("1.0.0", Some("2020-01-01T00:00:00Z")), ("1.1.0", None)]);
+        let options =
+            SelectOptions { minimum_release_age: TimeDelta::try_hours(24), ..Default::default() };
+        assert_eq!(matching_versions("^1.0.0", &metadata, &options), ["1.0.0"]);
+
+        let excluded = SelectOptions { release_age_exclude: vec!["pkg".to_string()], ..options };
+        assert_eq!(matching_versions("^1.0.0", &metadata, &excluded), ["1.1.0", "1.0.0"]);
+    }
 }
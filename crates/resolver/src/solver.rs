@@ -0,0 +1,794 @@
+use async_recursion::async_recursion;
+use futures::future::join_all;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use client::error::ClientError;
+use client::registry::PackageVersion;
+use client::versions::RequestPackage;
+use utils::logger::*;
+
+use crate::graph::DAGBuilder;
+use crate::lockfile::{
+    DependencyKind, LockedDependency, LockedPackage, Lockfile, package_key, package_name,
+    package_version,
+};
+use crate::overrides::{AppliedOverride, Overrides};
+use crate::peers::PeerIssue;
+use crate::semver::{self, Range, SemVer};
+use crate::sources;
+use crate::specifier::Specifier;
+
+const MAX_CANDIDATES: usize = 16;
+const MAX_PEER_ROUNDS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct Hop {
+    pub dependent: String,
+    pub name: String,
+    pub spec: String,
+    pub tried: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Reason {
+    InvalidSpecifier { name: String, spec: String },
+    NotFound { name: String },
+    NoMatchingVersion { name: String, spec: String },
+    SourceFailed { name: String, spec: String },
+    FetchFailed { name: String, error: ClientError },
+    UnsupportedPlatform { name: String, platform: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub chain: Vec<Hop>,
+    pub reason: Reason,
+}
+
+impl Conflict {
+    fn new(reason: Reason) -> Self {
+        Self { chain: Vec::new(), reason }
+    }
+
+    fn through(mut self, hop: Hop) -> Self {
+        self.chain.insert(0, hop);
+        self
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, hop) in self.chain.iter().enumerate() {
+            if i == 0 {
+                write!(f, "{} depends on {}@{}", hop.dependent, hop.name, hop.spec)?;
+            } else {
+                write!(f, ", which depends on {}@{}", hop.name, hop.spec)?;
+            }
+            if hop.tried > 1 {
+                write!(f, " ({} versions tried)", hop.tried)?;
+            }
+        }
+
+        if !self.chain.is_empty() {
+            f.write_str(", but ")?;
+        }
+
+        match &self.reason {
+            Reason::InvalidSpecifier { name, spec } => {
+                write!(f, "{name}@{spec} is not a valid specifier")
+            }
+            Reason::UnsupportedPlatform { name, platform } => {
+                write!(f, "{name} does not support {platform}")
+            }
+            Reason::NotFound { name } => write!(f, "{name} was not found in the registry"),
+            Reason::NoMatchingVersion { name, spec } => {
+                write!(f, "no version of {name} matches {spec}")
+            }
+            Reason::SourceFailed { name, spec } => {
+                write!(f, "{name} could not be fetched from {spec}")
+            }
+            Reason::FetchFailed { name, error } => {
+                write!(f, "{name} could not be fetched: {error}")
+            }
+        }
+    }
+}
+
+enum State {
+    Visiting,
+    Solved,
+    Failed(Conflict),
+}
+
+pub(crate) struct Dependency {
+    pub(crate) name: String,
+    pub(crate) spec: String,
+    pub(crate) kind: DependencyKind,
+}
+
+impl Dependency {
+    pub(crate) fn from_request(request: RequestPackage, kind: DependencyKind) -> Self {
+        Self {
+            name: request.name,
+            spec: request.version.unwrap_or_else(|| "latest".to_string()),
+            kind,
+        }
+    }
+
+    fn is_optional(&self) -> bool {
+        self.kind == DependencyKind::Optional
+    }
+}
+
+pub(crate) struct Solver<'a> {
+    builder: &'a DAGBuilder,
+    infos: HashMap<String, PackageVersion>,
+    states: HashMap<String, State>,
+    solved: HashMap<String, BTreeMap<String, LockedDependency>>,
+    sources: HashMap<String, String>,
+    chosen: HashMap<String, BTreeSet<String>>,
+    root: String,
+    path: Vec<String>,
+    overrides: Overrides,
+    pub(crate) peer_issues: Vec<PeerIssue>,
+}
+
+fn version_satisfies(key: &str, range: &str) -> bool {
+    match (SemVer::parse(package_version(key)), Range::parse(range)) {
+        (Some(version), Some(range)) => range.satisfies(&version),
+        _ => true,
+    }
+}
+
+fn dependencies_of(info: &PackageVersion) -> Vec<Dependency> {
+    let bundled = info.bundled();
+    let mut deps = Vec::new();
+
+    for (map, kind) in [
+        (&info.dependencies, DependencyKind::Prod),
+        (&info.optional_dependencies, DependencyKind::Optional),
+    ] {
+        let Some(map) = map else {
+            continue;
+        };
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort();
+        for (name, spec) in entries {
+            deps.retain(|d: &Dependency| &d.name != name);
+            deps.push(Dependency { name: name.clone(), spec: spec.clone(), kind });
+        }
+    }
+
+    deps.retain(|dep| !bundled.contains(&dep.name));
+    deps
+}
+
+fn registry_target(name: &str, spec: &str) -> Option<(String, String)> {
+    match Specifier::parse(spec)? {
+        Specifier::Range(req) | Specifier::Tag(req) => Some((name.to_string(), req)),
+        Specifier::Alias { name, spec } => match *spec {
+            Specifier::Range(req) | Specifier::Tag(req) => Some((name, req)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl<'a> Solver<'a> {
+    pub(crate) fn new(builder: &'a DAGBuilder, overrides: Overrides) -> Self {
+        Self {
+            builder,
+            infos: HashMap::new(),
+            states: HashMap::new(),
+            solved: HashMap::new(),
+            sources: HashMap::new(),
+            chosen: HashMap::new(),
+            root: String::new(),
+            path: Vec::new(),
+            overrides,
+            peer_issues: Vec::new(),
+        }
+    }
+
+    pub(crate) async fn solve(
+        &mut self,
+        root: &str,
+        deps: Vec<Dependency>,
+    ) -> Result<Lockfile, Conflict> {
+        let dependencies = self.solve_dependencies(root, deps).await?;
+
+        self.solved.insert(String::new(), dependencies);
+        self.root = root.to_string();
+        self.visit_peers(&mut vec![String::new()], &mut HashSet::new()).await;
+        let dependencies = self.solved.remove("").unwrap_or_default();
+
+        let mut lockfile =
+            Lockfile { overrides: self.overrides.to_map(), dependencies, ..Default::default() };
+        let mut stack: Vec<String> =
+            lockfile.dependencies.values().map(|d| d.package.clone()).collect();
+
+        while let Some(key) = stack.pop() {
+            if lockfile.packages.contains_key(&key) {
+                continue;
+            }
+            let Some(info) = self.infos.get(package_key(&key)) else {
+                continue;
+            };
+
+            let mut locked = LockedPackage::from_package(info);
+            locked.dependencies = self.solved.get(&key).cloned().unwrap_or_default();
+            stack.extend(locked.dependencies.values().map(|d| d.package.clone()));
+            lockfile.packages.insert(key, locked);
+        }
+
+        Ok(lockfile)
+    }
+
+    pub(crate) fn into_packages(self, lockfile: &Lockfile) -> HashMap<String, PackageVersion> {
+        lockfile
+            .packages
+            .keys()
+            .filter_map(|key| {
+                self.infos.get(package_key(key)).map(|info| (key.clone(), info.clone()))
+            })
+            .collect()
+    }
+
+    #[allow(clippy::double_must_use)]
+    #[async_recursion]
+    async fn resolve_node(&mut self, key: &str) -> Result<(), Conflict> {
+        match self.states.get(key) {
+            Some(State::Visiting | State::Solved) => return Ok(()),
+            Some(State::Failed(conflict)) => return Err(conflict.clone()),
+            None => {}
+        }
+
+        let Some(info) = self.infos.get(package_key(key)) else {
+            return Ok(());
+        };
+        let deps = dependencies_of(info);
+
+        self.states.insert(key.to_string(), State::Visiting);
+
+        self.path.push(key.to_string());
+        let result = self.solve_dependencies(key, deps).await;
+        self.path.pop();
+
+        match result {
+            Ok(edges) => {
+                self.solved.insert(key.to_string(), edges);
+                self.states.insert(key.to_string(), State::Solved);
+                let (name, version) = (package_name(key), package_version(key));
+                self.chosen.entry(name.to_string()).or_default().insert(version.to_string());
+                Ok(())
+            }
+            Err(conflict) => {
+                self.states.insert(key.to_string(), State::Failed(conflict.clone()));
+                Err(conflict)
+            }
+        }
+    }
+
+    async fn solve_dependencies(
+        &mut self,
+        dependent: &str,
+        deps: Vec<Dependency>,
+    ) -> Result<BTreeMap<String, LockedDependency>, Conflict> {
+        self.prefetch(&deps).await;
+
+        let mut edges = BTreeMap::new();
+        let mut alternatives: HashMap<String, Vec<String>> = HashMap::new();
+
+        for dep in deps {
+            let (spec, overridden) = self.apply_override(&dep).await;
+
+            match self.choose(dependent, &dep.name, &spec, dep.is_optional()).await {
+                Ok((key, rest)) => {
+                    let name = match dep.name.is_empty() {
+                        true => self
+                            .infos
+                            .get(package_key(&key))
+                            .map(|i| i.name.clone())
+                            .unwrap_or_default(),
+                        false => dep.name,
+                    };
+                    alternatives.insert(name.clone(), rest);
+                    edges.insert(
+                        name,
+                        LockedDependency {
+                            specifier: dep.spec,
+                            package: key,
+                            kind: dep.kind,
+                            overridden,
+                        },
+                    );
+                }
+                Err(conflict) if dep.is_optional() => {
+                    debug(format!("skipping optional dependency: {conflict}"), false);
+                }
+                Err(conflict) => return Err(conflict),
+            }
+        }
+
+        self.satisfy_peers(&mut edges, &mut alternatives).await;
+
+        Ok(edges)
+    }
+
+    async fn apply_override(&mut self, dep: &Dependency) -> (String, Option<AppliedOverride>) {
+        let Some(rule) = self.overrides.find(&self.path, &dep.name).cloned() else {
+            return (dep.spec.clone(), None);
+        };
+
+        if let Some(target) = rule.target()
+            && target.range.is_some()
+        {
+            let selected = self.candidates(&dep.name, &dep.spec).await.ok();
+            let matches = selected
+                .as_ref()
+                .and_then(|keys| keys.first())
+                .is_some_and(|key| target.matches_version(package_version(key)));
+            if !matches {
+                return (dep.spec.clone(), None);
+            }
+        }
+
+        debug(format!("overriding {}@{} with {}", dep.name, dep.spec, rule.specifier), false);
+        (rule.specifier.clone(), Some(rule.applied()))
+    }
+
+    async fn prefetch(&self, deps: &[Dependency]) {
+        let names: BTreeSet<String> = deps
+            .iter()
+            .filter_map(|dep| registry_target(&dep.name, &dep.spec).map(|(name, _)| name))
+            .collect();
+
+        join_all(names.iter().map(|name| async move {
+            let _permit = self.builder.semaphore.acquire().await.ok();
+            self.builder.get_cached_versions(name).await
+        }))
+        .await;
+    }
+
+    async fn choose(
+        &mut self,
+        dependent: &str,
+        name: &str,
+        spec: &str,
+        optional: bool,
+    ) -> Result<(String, Vec<String>), Conflict> {
+        let hop = |tried| Hop {
+            dependent: dependent.to_string(),
+            name: name.to_string(),
+            spec: spec.to_string(),
+            tried,
+        };
+
+        let mut candidates = self
+            .candidates(name, spec)
+            .await
+            .map_err(|reason| Conflict::new(reason).through(hop(1)))?;
+
+        if optional {
+            let platform = &self.builder.platform;
+            candidates.retain(|key| self.infos.get(key).is_none_or(|info| platform.supports(info)));
+            if candidates.is_empty() {
+                let reason = Reason::UnsupportedPlatform {
+                    name: name.to_string(),
+                    platform: platform.to_string(),
+                };
+                return Err(Conflict::new(reason).through(hop(1)));
+            }
+        }
+
+        let candidates: Vec<String> =
+            candidates.into_iter().map(|key| self.node_key(key)).collect();
+
+        let mut first_conflict = None;
+        for (i, key) in candidates.iter().enumerate() {
+            match self.resolve_node(key).await {
+                Ok(()) => return Ok((key.clone(), candidates[i + 1..].to_vec())),
+                Err(conflict) => {
+                    first_conflict.get_or_insert(conflict);
+                }
+            }
+        }
+
+        let conflict = first_conflict.unwrap_or_else(|| {
+            Conflict::new(Reason::NoMatchingVersion {
+                name: name.to_string(),
+                spec: spec.to_string(),
+            })
+        });
+
+        Err(conflict.through(hop(candidates.len())))
+    }
+
+    fn node_key(&self, key: String) -> String {
+        if self.overrides.is_empty() {
+            return key;
+        }
+
+        let mut ancestors = self.path.clone();
+        ancestors.push(key.clone());
+        match self.overrides.context(&ancestors) {
+            Some(context) => format!("{key}({context})"),
+            None => key,
+        }
+    }
+
+    async fn candidates(&mut self, name: &str, spec: &str) -> Result<Vec<String>, Reason> {
+        let Some(specifier) = Specifier::parse(spec) else {
+            return Err(Reason::InvalidSpecifier {
+                name: name.to_string(),
+                spec: spec.to_string(),
+            });
+        };
+
+        if let Some((target, req)) = registry_target(name, spec) {
+            return self.registry_candidates(&target, &req).await;
+        }
+
+        if let Some(key) = self.sources.get(spec) {
+            return Ok(vec![key.clone()]);
+        }
+
+        let info = match specifier {
+            Specifier::Git(git) => sources::resolve_git(&git).await,
+            Specifier::Tarball(url) => sources::resolve_tarball(&url).await,
+            Specifier::File(path) => sources::resolve_file(&path).await,
+            Specifier::Link(path) => sources::resolve_link(&path).await,
+            _ => None,
+        };
+
+        let Some(info) = info else {
+            return Err(Reason::SourceFailed { name: name.to_string(), spec: spec.to_string() });
+        };
+
+        let key = format!("{}@{}", info.name, info.version);
+        self.sources.insert(spec.to_string(), key.clone());
+        self.infos.insert(key.clone(), info);
+
+        Ok(vec![key])
+    }
+
+    async fn registry_candidates(&mut self, name: &str, req: &str) -> Result<Vec<String>, Reason> {
+        let metadata = match self.builder.get_cached_versions(name).await {
+            Ok(metadata) if !metadata.is_empty() => metadata,
+            Ok(_) | Err(ClientError::NotFound { .. }) => {
+                return Err(Reason::NotFound { name: name.to_string() });
+            }
+            Err(error) => return Err(Reason::FetchFailed { name: name.to_string(), error }),
+        };
+
+        let mut versions = semver::matching_versions(req, &metadata, &self.builder.options);
+        if versions.is_empty() {
+            return Err(Reason::NoMatchingVersion {
+                name: name.to_string(),
+                spec: req.to_string(),
+            });
+        }
+
+        if let Some(chosen) = self.chosen.get(name) {
+            versions.sort_by_key(|v| !chosen.contains(v));
+        }
+        versions.truncate(MAX_CANDIDATES);
+
+        let mut keys = Vec::with_capacity(versions.len());
+        for version in versions {
+            let key = format!("{name}@{version}");
+            if !self.infos.contains_key(&key)
+                && let Some(info) = metadata.get(&version)
+            {
+                self.infos.insert(key.clone(), info.clone());
+            }
+            keys.push(key);
+        }
+
+        Ok(keys)
+    }
+
+    async fn first_working(&mut self, candidates: Vec<String>) -> Option<String> {
+        for key in candidates {
+            if self.resolve_node(&key).await.is_ok() {
+                return Some(key);
+            }
+        }
+        None
+    }
+
+    async fn satisfy_peers(
+        &mut self,
+        edges: &mut BTreeMap<String, LockedDependency>,
+        alternatives: &mut HashMap<String, Vec<String>>,
+    ) {
+        for _ in 0..MAX_PEER_ROUNDS {
+            let mut changed = false;
+
+            for (child, dep) in edges.clone() {
+                let Some(peers) = self
+                    .infos
+                    .get(package_key(&dep.package))
+                    .and_then(|i| i.peer_dependencies.clone())
+                else {
+                    continue;
+                };
+
+                let mut peers: Vec<_> = peers.into_iter().collect();
+                peers.sort();
+
+                for (peer, range) in peers {
+                    let Some(provided) = edges.get(&peer).cloned() else {
+                        continue;
+                    };
+                    if provided.package == dep.package
+                        || version_satisfies(&provided.package, &range)
+                    {
+                        continue;
+                    }
+
+                    let peer_alternatives: Vec<String> = alternatives
+                        .get(&peer)
+                        .map(|alts| {
+                            alts.iter().filter(|k| version_satisfies(k, &range)).cloned().collect()
+                        })
+                        .unwrap_or_default();
+
+                    if let Some(key) = self.first_working(peer_alternatives).await {
+                        if let Some(alts) = alternatives.get_mut(&peer) {
+                            alts.retain(|k| k != &key);
+                        }
+                        edges.insert(peer, LockedDependency { package: key, ..provided });
+                        changed = true;
+                        break;
+                    }
+
+                    let child_alternatives: Vec<String> = alternatives
+                        .get(&child)
+                        .map(|alts| {
+                            alts.iter()
+                                .filter(|k| self.accepts_peer(k, &peer, &provided.package))
+                                .cloned()
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                    if let Some(key) = self.first_working(child_alternatives).await {
+                        if let Some(alts) = alternatives.get_mut(&child) {
+                            alts.retain(|k| k != &key);
+                        }
+                        edges.insert(child.clone(), LockedDependency { package: key, ..dep });
+                        changed = true;
+                        break;
+                    }
+                }
+
+                if changed {
+                    break;
+                }
+            }
+
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    #[allow(clippy::double_must_use)]
+    #[async_recursion]
+    async fn visit_peers(
+        &mut self,
+        path: &mut Vec<String>,
+        visited: &mut HashSet<(String, String)>,
+    ) {
+        let parent = path.last().cloned().unwrap_or_default();
+        let children: Vec<String> = self
+            .solved
+            .get(&parent)
+            .map(|edges| edges.values().map(|d| d.package.clone()).collect())
+            .unwrap_or_default();
+
+        for key in children {
+            if path.contains(&key) || !visited.insert((parent.clone(), key.clone())) {
+                continue;
+            }
+
+            let linked = self.link_peers(path, &key).await;
+            if linked != key
+                && let Some(edges) = self.solved.get_mut(&parent)
+            {
+                for edge in edges.values_mut().filter(|edge| edge.package == key) {
+                    edge.package = linked.clone();
+                }
+            }
+
+            path.push(linked);
+            self.visit_peers(path, visited).await;
+            path.pop();
+        }
+    }
+
+    async fn link_peers(&mut self, path: &[String], key: &str) -> String {
+        let Some(info) = self.infos.get(package_key(key)) else {
+            return key.to_string();
+        };
+        let Some(peers) = info.peer_dependencies.clone() else {
+            return key.to_string();
+        };
+        let meta = info.peer_dependencies_meta.clone().unwrap_or_default();
+
+        let mut peers: Vec<_> = peers.into_iter().collect();
+        peers.sort();
+
+        let mut linked = BTreeMap::new();
+        for (peer, range) in peers {
+            let provided = path
+                .iter()
+                .rev()
+                .find_map(|host| self.solved.get(host)?.get(&peer).map(|d| d.package.clone()));
+
+            let provided = match provided {
+                Some(provided) if provided == key => continue,
+                Some(provided) => {
+                    if !version_satisfies(&provided, &range) {
+                        self.peer_issues.push(PeerIssue {
+                            dependent: key.to_string(),
+                            name: peer.clone(),
+                            range: range.clone(),
+                            provided: Some(provided.clone()),
+                        });
+                    }
+                    provided
+                }
+                None if meta.get(&peer).is_some_and(|m| m.optional) => continue,
+                None => {
+                    let host = path.last().cloned().unwrap_or_default();
+                    let dependent = match host.is_empty() {
+                        true => self.root.clone(),
+                        false => host.clone(),
+                    };
+
+                    match self.choose(&dependent, &peer, &range, false).await {
+                        Ok((provided, _)) => {
+                            debug(format!("installing missing peer {provided} for {key}"), false);
+                            let edge = LockedDependency::new(range.clone(), provided.clone())
+                                .with_kind(DependencyKind::Peer);
+                            self.solved.entry(host).or_default().insert(peer.clone(), edge);
+                            provided
+                        }
+                        Err(conflict) => {
+                            debug(format!("could not install peer: {conflict}"), false);
+                            self.peer_issues.push(PeerIssue {
+                                dependent: key.to_string(),
+                                name: peer,
+                                range,
+                                provided: None,
+                            });
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            linked.insert(
+                peer,
+                LockedDependency::new(range, provided).with_kind(DependencyKind::Peer),
+            );
+        }
+
+        if linked.is_empty() {
+            return key.to_string();
+        }
+
+        let providers: Vec<&str> = linked.values().map(|edge| edge.package.as_str()).collect();
+        let variant = format!("{key}({})", providers.join(","));
+        if !self.solved.contains_key(&variant) {
+            let mut edges = self.solved.get(key).cloned().unwrap_or_default();
+            for (peer, edge) in linked {
+                edges.entry(peer).or_insert(edge);
+            }
+            self.solved.insert(variant.clone(), edges);
+        }
+
+        variant
+    }
+
+    fn accepts_peer(&self, key: &str, peer: &str, provided: &str) -> bool {
+        self.infos
+            .get(package_key(key))
+            .and_then(|info| info.peer_dependencies.as_ref())
+            .and_then(|peers| peers.get(peer))
+            .is_none_or(|range| version_satisfies(provided, range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use crate::graph::{Resolution, stub_registry};
+    use crate::manifest::Manifest;
+    use crate::semver::SelectOptions;
+
+    async fn resolve(registry: serde_json::Value, manifest: &str) -> Result<Resolution, Conflict> {
+        stub_registry(registry).await;
+        let manifest: Manifest = serde_json::from_str(manifest).expect("valid manifest");
+        DAGBuilder::with_options(SelectOptions::default()).resolve_manifest(&manifest).await
+    }
+
+    fn edge<'a>(lockfile: &'a Lockfile, key: &str, name: &str) -> &'a str {
+        &lockfile.packages[key].dependencies[name].package
+    }
+
+    #[tokio::test]
+    async fn peers_are_linked_per_providing_host() {
+        let registry = json!({
+            "peer-host": {"1.0.0": {}, "2.0.0": {}},
+            "peer-plugin": {"1.0.0": {"peerDependencies": {"peer-host": "*"}}},
+            "peer-x": {"1.0.0": {"dependencies": {"peer-host": "^1.0.0", "peer-plugin": "^1.0.0"}}},
+            "peer-y": {"1.0.0": {"dependencies": {"peer-host": "^2.0.0", "peer-plugin": "^1.0.0"}}},
+        });
+        let manifest = r#"{"dependencies": {"peer-x": "1.0.0", "peer-y": "1.0.0"}}"#;
+        let lockfile = resolve(registry, manifest).await.unwrap().lockfile;
+
+        let x = edge(&lockfile, "peer-x@1.0.0", "peer-plugin");
+        let y = edge(&lockfile, "peer-y@1.0.0", "peer-plugin");
+        assert_eq!(x, "peer-plugin@1.0.0(peer-host@1.0.0)");
+        assert_eq!(y, "peer-plugin@1.0.0(peer-host@2.0.0)");
+        assert_eq!(edge(&lockfile, x, "peer-host"), "peer-host@1.0.0");
+        assert_eq!(edge(&lockfile, y, "peer-host"), "peer-host@2.0.0");
+        assert!(!lockfile.packages.contains_key("peer-plugin@1.0.0"));
+    }
+
+    #[tokio::test]
+    async fn backtracks_to_older_candidates() {
+        let registry = json!({
+            "bt-a": {
+                "1.0.0": {"dependencies": {"bt-b": "^1.0.0"}},
+                "1.1.0": {"dependencies": {"bt-b": "^2.0.0"}},
+            },
+            "bt-b": {"1.0.0": {}},
+        });
+        let manifest = r#"{"name": "app", "dependencies": {"bt-a": "^1.0.0"}}"#;
+        let lockfile = resolve(registry, manifest).await.unwrap().lockfile;
+
+        assert_eq!(lockfile.dependencies["bt-a"].package, "bt-a@1.0.0");
+        assert_eq!(edge(&lockfile, "bt-a@1.0.0", "bt-b"), "bt-b@1.0.0");
+        assert!(!lockfile.packages.contains_key("bt-a@1.1.0"));
+    }
+
+    #[tokio::test]
+    async fn explains_unsatisfiable_chains() {
+        let registry = json!({
+            "cf-a": {
+                "1.0.0": {"dependencies": {"cf-b": "^1.0.0"}},
+                "1.1.0": {"dependencies": {"cf-b": "^1.0.0"}},
+            },
+            "cf-b": {"1.0.0": {"dependencies": {"cf-c": "^3.0.0"}}},
+            "cf-c": {"1.0.0": {}, "2.0.0": {}},
+        });
+        let manifest = r#"{"name": "app", "dependencies": {"cf-a": "^1.0.0"}}"#;
+        let conflict = resolve(registry, manifest).await.unwrap_err();
+
+        assert_eq!(
+            conflict.to_string(),
+            "app depends on cf-a@^1.0.0 (2 versions tried), which depends on cf-b@^1.0.0, \
+             which depends on cf-c@^3.0.0, but no version of cf-c matches ^3.0.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn explains_missing_packages() {
+        let manifest = r#"{"name": "app", "dependencies": {"nf-missing": "^1.0.0"}}"#;
+        let conflict = resolve(json!({"nf-missing": {}}), manifest).await.unwrap_err();
+
+        assert_eq!(
+            conflict.to_string(),
+            "app depends on nf-missing@^1.0.0, but nf-missing was not found in the registry"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use client::{git, integrity, registry::PackageVersion, tarball};
+use utils::logger::*;
+
+use crate::semver::{Range, SemVer};
+use crate::specifier::{GitSpec, Specifier};
+
+pub async fn resolve_git(spec: &GitSpec) -> Option<PackageVersion> {
+    let rev = match (&spec.committish, &spec.semver) {
+        (_, Some(range)) => {
+            let refs = git::ls_remote(&spec.url).await?;
+            let Some(sha) = select_git_tag(&refs, range) else {
+                error(format!("no tag in {} matches {range}", spec.url), false);
+                return None;
+            };
+            sha
+        }
+        (Some(committish), None) => {
+            let refs = git::ls_remote(&spec.url).await.unwrap_or_default();
+            ["refs/tags/", "refs/heads/"]
+                .iter()
+                .find_map(|prefix| refs.get(&format!("{prefix}{committish}")))
+                .cloned()
+                .unwrap_or_else(|| committish.clone())
+        }
+        (None, None) => "HEAD".to_string(),
+    };
+
+    let (sha, dir) = git::checkout(&spec.url, &rev).await?;
+    let mut pkg = read_directory(&dir)?;
+    pkg.dist.tarball = format!("git+{}#{sha}", spec.url);
+
+    Some(pkg)
+}
+
+fn select_git_tag(refs: &HashMap<String, String>, range: &str) -> Option<String> {
+    let range = Range::parse(range)?;
+
+    refs.iter()
+        .filter_map(|(name, sha)| {
+            let tag = name.strip_prefix("refs/tags/")?;
+            let version = SemVer::parse(tag)?;
+            range.satisfies(&version).then_some((version, sha))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, sha)| sha.clone())
+}
+
+pub async fn resolve_tarball(url: &str) -> Option<PackageVersion> {
+    let bytes = tarball::download(url).await?;
+
+    let Some(mut pkg) = tarball::read_manifest(&bytes) else {
+        error(format!("no package.json found in {url}"), false);
+        return None;
+    };
+    pkg.dist.tarball = url.to_string();
+    pkg.dist.integrity = Some(integrity::compute(&bytes).to_string());
+
+    Some(pkg)
+}
+
+pub async fn resolve_file(path: &Path) -> Option<PackageVersion> {
+    let path = absolutize(path)?;
+
+    let mut pkg = if path.is_dir() {
+        read_directory(&path)?
+    } else {
+        let Some(pkg) = tarball::read_manifest_file(&path) else {
+            error(format!("no package.json found in {}", path.display()), false);
+            return None;
+        };
+        pkg
+    };
+    pkg.dist.tarball = format!("file:{}", path.display());
+
+    Some(pkg)
+}
+
+pub async fn resolve_link(path: &Path) -> Option<PackageVersion> {
+    let path = absolutize(path)?;
+
+    let mut pkg = read_directory(&path)?;
+    pkg.dist.tarball = format!("link:{}", path.display());
+
+    Some(pkg)
+}
+
+fn absolutize(path: &Path) -> Option<PathBuf> {
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()?.join(rest),
+        Err(_) => path.to_path_buf(),
+    };
+
+    match std::path::absolute(&path) {
+        Ok(path) => Some(path),
+        Err(err) => {
+            error(format!("invalid path {}: {err}", path.display()), false);
+            None
+        }
+    }
+}
+
+fn read_directory(dir: &Path) -> Option<PackageVersion> {
+    let manifest_path = dir.join("package.json");
+
+    let Some(mut pkg) =
+        std::fs::read(&manifest_path).ok().and_then(|b| PackageVersion::from_manifest(&b))
+    else {
+        error(format!("could not read {}", manifest_path.display()), false);
+        return None;
+    };
+
+    for deps in [&mut pkg.dependencies, &mut pkg.optional_dependencies].into_iter().flatten() {
+        for spec in deps.values_mut() {
+            *spec = rebase_local_spec(dir, spec);
+        }
+    }
+
+    Some(pkg)
+}
+
+fn rebase_local_spec(base: &Path, spec: &str) -> String {
+    let (scheme, path) = match Specifier::parse(spec) {
+        Some(Specifier::File(path)) => ("file", path),
+        Some(Specifier::Link(path)) => ("link", path),
+        _ => return spec.to_string(),
+    };
+
+    if path.is_absolute() || path.starts_with("~") {
+        return spec.to_string();
+    }
+
+    format!("{scheme}:{}", base.join(path).display())
+}
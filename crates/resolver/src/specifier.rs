@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use crate::semver::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Specifier {
+    Range(String),
+    Tag(String),
+    Alias { name: String, spec: Box<Specifier> },
+    Git(GitSpec),
+    Tarball(String),
+    File(PathBuf),
+    Link(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSpec {
+    pub url: String,
+    pub committish: Option<String>,
+    pub semver: Option<String>,
+}
+
+const GIT_PREFIXES: [&str; 6] =
+    ["git+ssh://", "git+https://", "git+http://", "git+file://", "git://", "ssh://"];
+
+const HOSTS: [(&str, &str); 4] = [
+    ("github:", "https://github.com/"),
+    ("gitlab:", "https://gitlab.com/"),
+    ("bitbucket:", "https://bitbucket.org/"),
+    ("gist:", "https://gist.github.com/"),
+];
+
+impl Specifier {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+
+        if let Some(rest) = spec.strip_prefix("npm:") {
+            let (name, inner) = split_name_spec(rest);
+            let inner = Self::parse(inner)?;
+            if name.is_empty() || !matches!(inner, Self::Range(_) | Self::Tag(_)) {
+                return None;
+            }
+            return Some(Self::Alias { name: name.to_string(), spec: Box::new(inner) });
+        }
+
+        if let Some(path) = spec.strip_prefix("link:") {
+            return Some(Self::Link(PathBuf::from(path)));
+        }
+
+        if let Some(path) = spec.strip_prefix("file:") {
+            let path = path.strip_prefix("//").unwrap_or(path);
+            return Some(Self::File(PathBuf::from(path)));
+        }
+
+        if let Some(git) = parse_git(spec) {
+            return Some(Self::Git(git));
+        }
+
+        if spec.starts_with("http://") || spec.starts_with("https://") {
+            return Some(Self::Tarball(spec.to_string()));
+        }
+
+        if is_path(spec) {
+            return Some(Self::File(PathBuf::from(spec)));
+        }
+
+        if Range::parse(spec).is_some() {
+            return Some(Self::Range(spec.to_string()));
+        }
+
+        if is_tag(spec) {
+            return Some(Self::Tag(spec.to_string()));
+        }
+
+        None
+    }
+
+    pub fn is_registry(&self) -> bool {
+        matches!(self, Self::Range(_) | Self::Tag(_) | Self::Alias { .. })
+    }
+}
+
+pub fn split_name_spec(s: &str) -> (&str, &str) {
+    match s.get(1..).and_then(|rest| rest.find('@')) {
+        Some(pos) => (&s[..pos + 1], &s[pos + 2..]),
+        None => (s, ""),
+    }
+}
+
+fn split_committish(spec: &str) -> (&str, Option<String>, Option<String>) {
+    let Some((url, fragment)) = spec.split_once('#') else {
+        return (spec, None, None);
+    };
+
+    match fragment.strip_prefix("semver:") {
+        Some(range) => (url, None, Some(range.to_string())),
+        None if fragment.is_empty() => (url, None, None),
+        None => (url, Some(fragment.to_string()), None),
+    }
+}
+
+fn parse_git(spec: &str) -> Option<GitSpec> {
+    let (url, committish, semver) = split_committish(spec);
+
+    let url = if let Some(prefix) = GIT_PREFIXES.iter().find(|p| url.starts_with(**p)) {
+        let url = url.strip_prefix("git+").unwrap_or(url);
+        match prefix.contains("ssh") {
+            true => scp_url(url),
+            false => url.to_string(),
+        }
+    } else if let Some((prefix, base)) = HOSTS.iter().find(|(p, _)| url.starts_with(*p)) {
+        let path = &url[prefix.len()..];
+        format!("{base}{}.git", path.trim_end_matches(".git"))
+    } else if is_git_url(url) {
+        url.to_string()
+    } else if is_github_shorthand(url) {
+        format!("https://github.com/{}.git", url.trim_end_matches(".git"))
+    } else {
+        return None;
+    };
+
+    Some(GitSpec { url, committish, semver })
+}
+
+fn is_git_url(url: &str) -> bool {
+    let http = (url.starts_with("https://") || url.starts_with("http://")) && url.ends_with(".git");
+    let scp = url.starts_with("git@") && url.contains(':');
+    http || scp
+}
+
+fn scp_url(url: &str) -> String {
+    let Some(rest) = url.strip_prefix("ssh://") else {
+        return url.to_string();
+    };
+
+    match rest.split_once(':') {
+        Some((_, path)) if !path.starts_with(|c: char| c.is_ascii_digit()) => rest.to_string(),
+        _ => url.to_string(),
+    }
+}
+
+fn is_github_shorthand(s: &str) -> bool {
+    let mut parts = s.split('/');
+    let (Some(owner), Some(repo), None) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+
+    let valid = |p: &str| {
+        !p.is_empty()
+            && !p.starts_with('.')
+            && p.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    !s.starts_with('@') && valid(owner) && valid(repo)
+}
+
+fn is_path(s: &str) -> bool {
+    s.starts_with('.')
+        || s.starts_with('/')
+        || s.starts_with("~/")
+        || s.ends_with(".tgz")
+        || s.ends_with(".tar.gz")
+        || s.ends_with(".tar")
+}
+
+fn is_tag(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::graph::DependencyGraph;
+use crate::lockfile::LockedDependency;
+use crate::overrides::AppliedOverride;
+use crate::semver::{Range, SemVer};
+use crate::specifier::split_name_spec;
+
+const MAX_CHAINS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    pub name: String,
+    pub specifier: String,
+    pub package: String,
+    #[serde(rename = "override", skip_serializing_if = "Option::is_none")]
+    pub overridden: Option<AppliedOverride>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub package: String,
+    pub chains: Vec<Vec<Step>>,
+    pub truncated: bool,
+}
+
+pub fn explain(graph: &DependencyGraph, query: &str) -> Vec<Explanation> {
+    let (name, filter) = split_name_spec(query);
+    let range = Range::parse(filter);
+
+    let targets: Vec<&String> = graph
+        .nodes
+        .iter()
+        .filter(|(key, node)| {
+            node.info.name == name
+                && (filter.is_empty()
+                    || key.as_str() == query
+                    || range
+                        .as_ref()
+                        .zip(SemVer::parse(&node.info.version))
+                        .is_some_and(|(range, version)| range.satisfies(&version)))
+        })
+        .map(|(key, _)| key)
+        .collect();
+
+    targets
+        .into_iter()
+        .map(|target| {
+            let reaches = reaching(graph, target);
+            let mut walker =
+                Walker { graph, target, reaches, chains: Vec::new(), truncated: false };
+            walker.walk(&graph.root_edges, &mut Vec::new(), &mut HashSet::new());
+            Explanation {
+                package: target.clone(),
+                chains: walker.chains,
+                truncated: walker.truncated,
+            }
+        })
+        .collect()
+}
+
+fn reaching(graph: &DependencyGraph, target: &str) -> HashSet<String> {
+    let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (key, node) in &graph.nodes {
+        for dep in &node.dependencies {
+            dependents.entry(dep.as_str()).or_default().push(key.as_str());
+        }
+    }
+
+    let mut reaches = HashSet::from([target.to_string()]);
+    let mut stack = vec![target];
+    while let Some(key) = stack.pop() {
+        for dependent in dependents.get(key).into_iter().flatten() {
+            if reaches.insert(dependent.to_string()) {
+                stack.push(dependent);
+            }
+        }
+    }
+
+    reaches
+}
+
+struct Walker<'a> {
+    graph: &'a DependencyGraph,
+    target: &'a str,
+    reaches: HashSet<String>,
+    chains: Vec<Vec<Step>>,
+    truncated: bool,
+}
+
+impl Walker<'_> {
+    fn walk(
+        &mut self,
+        edges: &BTreeMap<String, LockedDependency>,
+        path: &mut Vec<Step>,
+        visiting: &mut HashSet<String>,
+    ) {
+        for (name, edge) in edges {
+            if self.chains.len() >= MAX_CHAINS {
+                self.truncated = true;
+                return;
+            }
+            if !self.reaches.contains(&edge.package) || visiting.contains(&edge.package) {
+                continue;
+            }
+
+            path.push(Step {
+                name: name.clone(),
+                specifier: edge.specifier.clone(),
+                package: edge.package.clone(),
+                overridden: edge.overridden.clone(),
+            });
+
+            if edge.package == self.target {
+                self.chains.push(path.clone());
+            } else if let Some(node) = self.graph.nodes.get(&edge.package) {
+                visiting.insert(edge.package.clone());
+                self.walk(&node.edges, path, visiting);
+                visiting.remove(&edge.package);
+            }
+
+            path.pop();
+        }
+    }
+}
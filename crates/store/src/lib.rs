@@ -1,7 +1,14 @@
-use client::registry::PackageVersion;
+use client::auth::authorize;
+use client::error::ClientError;
+use client::git;
+use client::http;
+use client::integrity::Integrity;
+use client::registry::{PackageVersion, tarball_url};
+use client::retry;
 use client::versions::RequestPackage;
 use flate2::read::GzDecoder;
 use reqwest::Client;
+use sha2::{Digest, Sha256};
 use tar::Archive;
 
 use futures::stream::FuturesUnordered;
@@ -19,8 +26,8 @@ use std::{
     collections::HashSet,
     error::Error,
     fs::{
-        File, create_dir_all, metadata, read_dir, read_to_string, remove_dir_all, remove_file,
-        rename, write,
+        File, copy, create_dir_all, metadata, read_dir, read_to_string, remove_dir_all,
+        remove_file, rename, write,
     },
     io::BufReader,
     path::{Path, PathBuf},
@@ -49,6 +56,81 @@ fn unsanitize_name(s: &str) -> String {
     s.replace('+', "/")
 }
 
+fn digest(source: &str) -> String {
+    Sha256::digest(source.as_bytes())[..8].iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn is_registry_tarball(package: &PackageVersion) -> bool {
+    package.dist.tarball.contains(&format!("/{}/-/", package.name))
+}
+
+fn is_local_copy(package: &PackageVersion) -> bool {
+    package.dist.tarball.starts_with("file:")
+}
+
+pub fn store_key(package: &PackageVersion) -> String {
+    let source = package.dist.tarball.as_str();
+    let origin = if let Some(rest) = source.strip_prefix("git+") {
+        rest.rsplit_once('#').map(|(_, sha)| format!("git.{sha}"))
+    } else if let Some(path) = source.strip_prefix("file:") {
+        Some(format!("file.{}", digest(path)))
+    } else if let Some(path) = source.strip_prefix("link:") {
+        Some(format!("link.{}", digest(path)))
+    } else if !is_registry_tarball(package) {
+        Some(format!("url.{}", digest(package.dist.integrity.as_deref().unwrap_or(source))))
+    } else {
+        None
+    };
+
+    match origin {
+        Some(origin) => format!("{}@{}+{origin}", package.name, package.version),
+        None => format!("{}@{}", package.name, package.version),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path, bundled: Option<&[String]>) -> std::io::Result<()> {
+    create_dir_all(to)?;
+
+    for entry in read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" {
+            continue;
+        }
+
+        let dest = to.join(&name);
+        if name == "node_modules"
+            && let Some(bundled) = bundled
+        {
+            for dep in bundled {
+                let source = entry.path().join(dep);
+                if source.is_dir() {
+                    copy_dir(&source, &dest.join(dep), None)?;
+                }
+            }
+            continue;
+        }
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest, bundled.map(|_| &[][..]))?;
+        } else {
+            copy(entry.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn link_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
+
 impl Store {
     fn load_index_sync(store_path: &Path) -> Option<HashSet<String>> {
         let index_path = store_path.join(".index");
@@ -92,8 +174,10 @@ impl Store {
 
     pub fn new() -> Self {
         let home_dir = dirs::home_dir().unwrap();
-        let store_path = home_dir.join(".qipi").join("store");
+        Self::at(home_dir.join(".qipi").join("store"))
+    }
 
+    fn at(store_path: PathBuf) -> Self {
         if !store_path.exists() {
             create_dir_all(&store_path).unwrap();
             info("Store directory created", false);
@@ -126,10 +210,10 @@ impl Store {
     async fn get_cached_packages(&self) -> HashSet<String> {
         {
             let cache = self.package_cache.read().await;
-            if let Some((packages, timestamp)) = cache.as_ref() {
-                if timestamp.elapsed() < STORE_CACHE_TTL {
-                    return packages.clone();
-                }
+            if let Some((packages, timestamp)) = cache.as_ref()
+                && timestamp.elapsed() < STORE_CACHE_TTL
+            {
+                return packages.clone();
             }
         }
 
@@ -192,29 +276,33 @@ impl Store {
     }
 
     fn create_client() -> Client {
-        Client::builder()
-            .tcp_keepalive(Duration::from_secs(60))
-            .pool_max_idle_per_host(100)
-            .pool_idle_timeout(Duration::from_secs(300))
-            .timeout(Duration::from_secs(8))
-            .connect_timeout(Duration::from_secs(3))
-            .tcp_nodelay(true)
-            .build()
-            .unwrap_or_else(|_| Client::new())
+        http::build(
+            http::client_builder()
+                .pool_max_idle_per_host(100)
+                .pool_idle_timeout(Duration::from_secs(300))
+                .timeout(Duration::from_secs(8))
+                .connect_timeout(Duration::from_secs(3))
+                .tcp_nodelay(true),
+        )
     }
 
-    pub async fn install_packages(&self, packages: Vec<PackageVersion>) -> Vec<String> {
+    pub async fn install_packages(
+        &self,
+        packages: Vec<PackageVersion>,
+    ) -> (Vec<String>, Vec<String>) {
         let existing_packages = self.get_cached_packages().await;
+        let mut seen = HashSet::new();
         let packages_to_install: Vec<_> = packages
             .into_iter()
             .filter(|pkg| {
-                let package_key = format!("{}@{}", pkg.name, pkg.version);
-                !existing_packages.contains(&package_key)
+                let package_key = store_key(pkg);
+                (is_local_copy(pkg) || !existing_packages.contains(&package_key))
+                    && seen.insert(package_key)
             })
             .collect();
 
         if packages_to_install.is_empty() {
-            return Vec::new();
+            return (Vec::new(), Vec::new());
         }
 
         info(format!("Installing {} new packages...", packages_to_install.len()), false);
@@ -225,20 +313,34 @@ impl Store {
             let extract_sem = self.extract_semaphore.clone();
             let s = self;
             futs.push(async move {
-                let _download_permit = download_sem.acquire().await.ok()?;
-                let tarball_path = s.download_package(&pkg).await.ok()?;
+                let key = store_key(&pkg);
+                let _download_permit = download_sem.acquire().await.map_err(|_| key.clone())?;
+                let tarball_path = match s.download_package(&pkg).await {
+                    Ok(tarball_path) => tarball_path,
+                    Err(err) => {
+                        error(format!("{}@{}: {err}", pkg.name, pkg.version), false);
+                        return Err(key);
+                    }
+                };
 
                 drop(_download_permit);
-                let _extract_permit = extract_sem.acquire().await.ok()?;
-                s.extract_package(tarball_path, &pkg).await.ok()?;
-                Some(format!("{}@{}", pkg.name, pkg.version))
+                if let Some(tarball_path) = tarball_path {
+                    let _extract_permit = extract_sem.acquire().await.map_err(|_| key.clone())?;
+                    if let Err(err) = s.extract_package(tarball_path, &pkg).await {
+                        error(format!("{}@{}: {err}", pkg.name, pkg.version), false);
+                        return Err(key);
+                    }
+                }
+                Ok(key)
             });
         }
 
         let mut installed: Vec<String> = Vec::new();
+        let mut failed: Vec<String> = Vec::new();
         while let Some(result) = futs.next().await {
-            if let Some(k) = result {
-                installed.push(k);
+            match result {
+                Ok(k) => installed.push(k),
+                Err(k) => failed.push(k),
             }
         }
 
@@ -264,23 +366,60 @@ impl Store {
             .ok();
         }
 
-        installed
+        (installed, failed)
     }
 
     async fn download_package(
         &self,
         package: &PackageVersion,
-    ) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync>> {
         let package_key = format!("{}@{}", package.name, package.version);
-        let sanitized_key = sanitize_package_key(&package_key);
-        let package_path = self.store_path.join(&sanitized_key);
+        let package_path = self.store_path.join(sanitize_package_key(&store_key(package)));
+        let source = package.dist.tarball.as_str();
+
+        if let Some(target) = source.strip_prefix("link:") {
+            if package_path.exists() {
+                return Ok(None);
+            }
+            link_dir(Path::new(target), &package_path)?;
+            return Ok(None);
+        }
 
+        if is_local_copy(package) && package_path.exists() {
+            remove_dir_all(&package_path)?;
+        }
         create_dir_all(&package_path)?;
 
-        let response = self.client.get(&package.dist.tarball).send().await?;
-        if !response.status().is_success() {
-            return Err("Failed to download tarball".into());
+        if let Some(path) = source.strip_prefix("file:") {
+            let path = PathBuf::from(path);
+            if path.is_dir() {
+                let bundled = package.bundled();
+                spawn_blocking(move || copy_dir(&path, &package_path, Some(&bundled))).await??;
+                return Ok(None);
+            }
+
+            let tarball_path = package_path.join("package.tgz");
+            tokio::fs::copy(&path, &tarball_path).await?;
+            return Ok(Some(tarball_path));
+        }
+
+        if let Some(rest) = source.strip_prefix("git+") {
+            let (url, sha) = rest.rsplit_once('#').ok_or("Missing commit in git source")?;
+            let (_, checkout) =
+                git::checkout(url, sha).await.ok_or("Failed to check out git source")?;
+            let bundled = package.bundled();
+            spawn_blocking(move || copy_dir(&checkout, &package_path, Some(&bundled))).await??;
+            return Ok(None);
+        }
+
+        let url = tarball_url(&package.name, &package.dist.tarball);
+        let response = retry::send(&url, || authorize(self.client.get(&url), &url)).await?;
+
+        let expected = Integrity::for_dist(&package.dist);
+        if expected.is_none() {
+            warn(format!("{package_key} has no integrity, skipping verification"), false);
         }
+        let mut hasher = expected.as_ref().map(Integrity::hasher);
 
         let tarball_path = package_path.join("package.tgz");
         let mut file = TokioFile::create(&tarball_path).await?;
@@ -288,11 +427,29 @@ impl Store {
         let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
             let chunk = chunk?;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&chunk);
+            }
             file.write_all(&chunk).await?;
         }
 
         file.flush().await?;
-        Ok(tarball_path)
+
+        if let (Some(expected), Some(hasher)) = (expected, hasher)
+            && let actual = hasher.finish()
+            && !expected.matches(&actual)
+        {
+            drop(file);
+            let _ = remove_dir_all(&package_path);
+            return Err(ClientError::Integrity {
+                url,
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            }
+            .into());
+        }
+
+        Ok(Some(tarball_path))
     }
 
     async fn extract_package(
@@ -300,9 +457,7 @@ impl Store {
         tarball_path: PathBuf,
         package: &PackageVersion,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let package_key = format!("{}@{}", package.name, package.version);
-        let sanitized_key = sanitize_package_key(&package_key);
-        let package_path = self.store_path.join(&sanitized_key);
+        let package_path = self.store_path.join(sanitize_package_key(&store_key(package)));
 
         spawn_blocking(move || -> Result<(), Box<dyn Error + Send + Sync>> {
             let file = File::open(&tarball_path)?;
@@ -313,9 +468,16 @@ impl Store {
             archive.set_preserve_mtime(false);
             archive.unpack(&package_path)?;
 
-            let inner_path = package_path.join("package");
-            if inner_path.exists() {
-                for entry in read_dir(&inner_path)? {
+            let entries: Vec<_> = read_dir(&package_path)?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path != &tarball_path)
+                .collect();
+
+            if let [inner_path] = entries.as_slice()
+                && inner_path.is_dir()
+            {
+                for entry in read_dir(inner_path)? {
                     let entry = entry?;
                     let dest = package_path.join(entry.file_name());
                     if !dest.exists() {
@@ -331,7 +493,7 @@ impl Store {
         .await?
     }
 
-    pub async fn add_packages(&self, packages: Vec<PackageVersion>) -> Vec<String> {
+    pub async fn add_packages(&self, packages: Vec<PackageVersion>) -> (Vec<String>, Vec<String>) {
         self.install_packages(packages).await
     }
 
@@ -439,3 +601,160 @@ impl Default for Store {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use client::integrity;
+    use client::registry::DistInfo;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::process::Command;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qipi-store-{}-{name}", std::process::id()));
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn package(source: String, integrity: Option<String>) -> PackageVersion {
+        PackageVersion {
+            name: "dep".to_string(),
+            version: "1.0.0".to_string(),
+            dist: DistInfo { tarball: source, integrity, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=qipi", "-c", "user.email=qipi@localhost"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(work: &Path, file: &str) -> String {
+        write(work.join(file), file).unwrap();
+        git(work, &["add", "."]);
+        git(work, &["commit", "--quiet", "-m", file]);
+        git(work, &["push", "--quiet", "origin", "HEAD"]);
+        git(work, &["rev-parse", "HEAD"])
+    }
+
+    fn tarball() -> Vec<u8> {
+        let manifest = br#"{"name": "dep", "version": "1.0.0"}"#;
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, "package/package.json", &manifest[..]).unwrap();
+        archive.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn serve(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0; 4096]);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+        format!("http://{addr}/dep.tgz")
+    }
+
+    #[tokio::test]
+    async fn git_sources_are_keyed_by_commit() {
+        let root = scratch("git");
+        let bare = root.join("dep.git");
+        let work = root.join("work");
+        git(&root, &["init", "--quiet", "--bare", "dep.git"]);
+        git(&root, &["clone", "--quiet", bare.to_str().unwrap(), "work"]);
+        write(work.join("package.json"), r#"{"name": "dep", "version": "1.0.0"}"#).unwrap();
+
+        let store = Store::at(root.join("store"));
+        let url = format!("file://{}", bare.display());
+
+        let first = commit(&work, "first.js");
+        let installed = store.install_packages(vec![package(format!("git+{url}#{first}"), None)]);
+        assert_eq!(installed.await.0, [format!("dep@1.0.0+git.{first}")]);
+
+        let second = commit(&work, "second.js");
+        let installed = store.install_packages(vec![package(format!("git+{url}#{second}"), None)]);
+        assert_eq!(installed.await.0, [format!("dep@1.0.0+git.{second}")]);
+
+        let path = root.join("store").join(format!("dep@1.0.0+git.{second}"));
+        assert!(path.join("second.js").exists());
+        assert!(!root.join("store").join("dep@1.0.0").exists());
+    }
+
+    #[tokio::test]
+    async fn file_directories_are_refreshed() {
+        let root = scratch("file");
+        let source = root.join("dep");
+        create_dir_all(&source).unwrap();
+        write(source.join("package.json"), r#"{"name": "dep", "version": "1.0.0"}"#).unwrap();
+        write(source.join("index.js"), "one").unwrap();
+
+        let store = Store::at(root.join("store"));
+        let pkg = package(format!("file:{}", source.display()), None);
+        let key = store_key(&pkg);
+        assert_eq!(key, format!("dep@1.0.0+file.{}", digest(&source.display().to_string())));
+
+        assert_eq!(store.install_packages(vec![pkg.clone()]).await.0, [key.as_str()]);
+        write(source.join("index.js"), "two").unwrap();
+        assert_eq!(store.install_packages(vec![pkg]).await.0, [key.as_str()]);
+
+        let copied = read_to_string(root.join("store").join(&key).join("index.js")).unwrap();
+        assert_eq!(copied, "two");
+    }
+
+    #[tokio::test]
+    async fn remote_tarballs_are_keyed_by_integrity() {
+        let root = scratch("tarball");
+        let body = tarball();
+        let integrity = integrity::compute(&body).to_string();
+        let pkg = package(serve(body), Some(integrity.clone()));
+        let key = format!("dep@1.0.0+url.{}", digest(&integrity));
+
+        let store = Store::at(root.join("store"));
+        assert_eq!(store.install_packages(vec![pkg.clone()]).await.0, [key.as_str()]);
+        assert!(root.join("store").join(&key).join("package.json").exists());
+        assert!(!root.join("store").join("dep@1.0.0").exists());
+
+        assert!(store.install_packages(vec![pkg]).await.0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn tarballs_failing_verification_are_reported() {
+        let root = scratch("integrity");
+        let integrity = integrity::compute(b"something else").to_string();
+        let pkg = package(serve(tarball()), Some(integrity));
+        let key = store_key(&pkg);
+
+        let store = Store::at(root.join("store"));
+        let (installed, failed) = store.install_packages(vec![pkg]).await;
+        assert!(installed.is_empty());
+        assert_eq!(failed, [key.as_str()]);
+        assert!(!root.join("store").join(&key).exists());
+    }
+
+    #[test]
+    fn registry_packages_keep_their_name_and_version() {
+        let url = "https://registry.npmjs.org/dep/-/dep-1.0.0.tgz".to_string();
+        assert_eq!(store_key(&package(url, None)), "dep@1.0.0");
+    }
+}